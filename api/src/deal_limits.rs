@@ -0,0 +1,241 @@
+use crate::settings::DealLimits;
+use async_graphql::ErrorExtensions;
+use base::jwt::Role;
+use chrono::{DateTime, TimeDelta, Utc};
+use entity::{active_transactions, offer_audit, sea_orm_active_enums::Action, user_limits};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbBackend, DbErr, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Statement, prelude::Uuid,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DealLimitKind {
+    Concurrent,
+    Daily,
+    Weekly,
+    OfferCooldown,
+}
+
+impl DealLimitKind {
+    fn code(&self) -> &'static str {
+        match self {
+            DealLimitKind::Concurrent => "CONCURRENT",
+            DealLimitKind::Daily => "DAILY",
+            DealLimitKind::Weekly => "WEEKLY",
+            DealLimitKind::OfferCooldown => "OFFER_COOLDOWN",
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DealLimitError {
+    #[error("{} deal limit reached", .kind.code().to_lowercase())]
+    LimitReached {
+        kind: DealLimitKind,
+        next_redeem_at: Option<DateTime<Utc>>,
+    },
+    #[error(transparent)]
+    DbError(#[from] DbErr),
+}
+
+impl ErrorExtensions for DealLimitError {
+    fn extend(&self) -> async_graphql::Error {
+        async_graphql::Error::new(self.to_string()).extend_with(|_, e| match self {
+            DealLimitError::LimitReached {
+                kind,
+                next_redeem_at,
+            } => {
+                e.set("code", "DEAL_LIMIT_REACHED");
+                e.set("limit", kind.code());
+                if let Some(next_redeem_at) = next_redeem_at {
+                    e.set("nextRedeemAt", next_redeem_at.to_rfc3339());
+                }
+            }
+            DealLimitError::DbError(_) => e.set("code", "INTERNAL_SERVER_ERROR"),
+        })
+    }
+}
+
+// None is unlimited
+#[derive(Debug, Default)]
+pub struct UserDealLimits {
+    pub concurrent: Option<i32>,
+    pub daily: Option<i32>,
+    pub weekly: Option<i32>,
+    pub offer_cooldown: Option<TimeDelta>,
+}
+
+impl UserDealLimits {
+    pub async fn for_user<C>(
+        db: &C,
+        defaults: &DealLimits,
+        roles: &[Role],
+        user_id: Uuid,
+    ) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if roles.contains(&Role::Admin) {
+            return Ok(Self::default());
+        }
+
+        let overrides = user_limits::Entity::find_by_id(user_id).one(db).await?;
+        let overrides = overrides.as_ref();
+
+        Ok(Self {
+            concurrent: overrides
+                .and_then(|o| o.concurrent_limit)
+                .or(Some(defaults.concurrent)),
+            daily: overrides.and_then(|o| o.daily_limit).or(defaults.daily),
+            weekly: overrides.and_then(|o| o.weekly_limit).or(defaults.weekly),
+            offer_cooldown: overrides
+                .and_then(|o| o.offer_cooldown_seconds)
+                .or(defaults.offer_cooldown_seconds)
+                .map(TimeDelta::seconds),
+        })
+    }
+
+    // held until the transaction ends. take it before check and keep it until the deal's
+    // audit row is in the same transaction, or two requests can both pass check
+    pub async fn lock<C>(db: &C, user_id: Uuid) -> Result<(), DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        db.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT pg_advisory_xact_lock(hashtext($1))",
+            [user_id.to_string().into()],
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    // every limit is checked so the error can say when all of them allow another deal
+    pub async fn check<C>(
+        &self,
+        db: &C,
+        user_id: Uuid,
+        offer_proposition_id: i64,
+    ) -> Result<(), DealLimitError>
+    where
+        C: ConnectionTrait,
+    {
+        let mut reached = vec![];
+
+        if let Some(limit) = self.concurrent {
+            // derived rather than read from concurrent_active_deals, which can drift
+            let active = active_transactions::Entity::find()
+                .filter(active_transactions::Column::UserId.eq(user_id))
+                .count(db)
+                .await?;
+
+            if let Some(over) = active.checked_sub(u64::try_from(limit).unwrap_or_default()) {
+                let next = concurrent_reopens_at(db, user_id, over).await?;
+                reached.push((DealLimitKind::Concurrent, next));
+            }
+        }
+
+        let windowed = [
+            (DealLimitKind::Daily, self.daily, TimeDelta::days(1), None),
+            (
+                DealLimitKind::Weekly,
+                self.weekly,
+                TimeDelta::weeks(1),
+                None,
+            ),
+            (
+                DealLimitKind::OfferCooldown,
+                self.offer_cooldown.map(|_| 1),
+                self.offer_cooldown.unwrap_or_default(),
+                Some(offer_proposition_id),
+            ),
+        ];
+
+        for (kind, limit, window, offer_proposition_id) in windowed {
+            let Some(limit) = limit else {
+                continue;
+            };
+
+            if limit <= 0 {
+                reached.push((kind, None));
+                continue;
+            }
+
+            if let Some(next) =
+                window_reopens_at(db, user_id, offer_proposition_id, limit, window).await?
+            {
+                reached.push((kind, Some(next)));
+            }
+        }
+
+        // a limit that never reopens on its own wins, then the one reopening last
+        let latest = reached
+            .into_iter()
+            .max_by_key(|(_, next_redeem_at)| (next_redeem_at.is_none(), *next_redeem_at));
+
+        match latest {
+            Some((kind, next_redeem_at)) => Err(DealLimitError::LimitReached {
+                kind,
+                next_redeem_at,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+// deals can be extended, so this goes by when their accounts unlock rather than when they
+// were added. over is how many active deals are past the limit, one more than that has to
+// end before another is allowed
+async fn concurrent_reopens_at<C>(
+    db: &C,
+    user_id: Uuid,
+    over: u64,
+) -> Result<Option<DateTime<Utc>>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let deal = active_transactions::Entity::find()
+        .filter(active_transactions::Column::UserId.eq(user_id))
+        .order_by_asc(active_transactions::Column::UnlockAt)
+        .offset(over)
+        .one(db)
+        .await?;
+
+    Ok(deal.map(|d| d.unlock_at.and_utc()))
+}
+
+// once the limit-th most recent deal leaves the window another one is allowed,
+// None when there are fewer than limit deals in it
+async fn window_reopens_at<C>(
+    db: &C,
+    user_id: Uuid,
+    offer_proposition_id: Option<i64>,
+    limit: i32,
+    window: TimeDelta,
+) -> Result<Option<DateTime<Utc>>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let Ok(offset) = u64::try_from(limit - 1) else {
+        return Ok(None);
+    };
+
+    let since = Utc::now().naive_utc() - window;
+    let mut query = offer_audit::Entity::find()
+        .filter(offer_audit::Column::UserId.eq(user_id))
+        .filter(offer_audit::Column::Action.eq(Action::Add))
+        .filter(offer_audit::Column::CreatedAt.gt(since));
+
+    if let Some(offer_proposition_id) = offer_proposition_id {
+        query = query.filter(offer_audit::Column::PropositionId.eq(offer_proposition_id));
+    }
+
+    let audit = query
+        .order_by_desc(offer_audit::Column::Id)
+        .offset(offset)
+        .one(db)
+        .await?;
+
+    Ok(audit.map(|a| a.created_at.and_utc() + window))
+}
@@ -0,0 +1,465 @@
+use super::edit_original_response;
+use crate::{
+    event_manager::EventManager,
+    graphql::{
+        mutations::offers::{add_to_deal_stack, types::AddOfferInput},
+        queries::locations::{dataloader::find_nearby_stores, types::LocationRequest},
+    },
+    settings::Settings,
+};
+use base::{constants::MACCAS_ACCOUNT_REFRESH_FAILURE, jwt::Role};
+use entity::{account_lock, accounts, discord_users, offer_details, offers, points, stores};
+use reqwest_middleware::ClientWithMiddleware;
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, JoinType, Order, QueryFilter,
+    QueryOrder, QuerySelect, QueryTrait, RelationTrait, Set, TransactionTrait, Unchanged,
+    prelude::Expr, sea_query::extension::postgres::PgExpr,
+};
+use twilight_model::{
+    application::{
+        command::{Command, CommandType},
+        interaction::{
+            Interaction, InteractionData, InteractionType,
+            application_command::{CommandDataOption, CommandOptionValue},
+        },
+    },
+    channel::message::MessageFlags,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+use twilight_util::builder::{
+    InteractionResponseDataBuilder,
+    command::{CommandBuilder, StringBuilder, SubCommandBuilder},
+};
+
+// keeps a listing well under discord's 2000 character limit
+const MAX_LISTED: usize = 20;
+const POINTS_ACCOUNTS: u64 = 5;
+const NEARBY_STORES: usize = 5;
+const NOT_LINKED: &str = "Your Discord account isn't linked or the link has expired, create a link code in the web app and run /link";
+
+pub fn definitions() -> Vec<Command> {
+    vec![
+        CommandBuilder::new("deals", "Deals available right now", CommandType::ChatInput)
+            .option(StringBuilder::new(
+                "category",
+                "Only show deals in this category",
+            ))
+            .build(),
+        CommandBuilder::new("deal", "Get a code for a deal", CommandType::ChatInput)
+            .option(StringBuilder::new("name", "Deal name as shown by /deals").required(true))
+            .option(StringBuilder::new("store", "Store id or name").required(true))
+            .build(),
+        CommandBuilder::new(
+            "points",
+            "Accounts with the most points",
+            CommandType::ChatInput,
+        )
+        .build(),
+        CommandBuilder::new("stores", "Find stores", CommandType::ChatInput)
+            .option(
+                SubCommandBuilder::new("near", "Stores near a suburb")
+                    .option(StringBuilder::new("suburb", "Suburb or postcode").required(true)),
+            )
+            .build(),
+        CommandBuilder::new("link", "Link your Discord account", CommandType::ChatInput)
+            .option(StringBuilder::new("code", "Link code from the web app").required(true))
+            .build(),
+    ]
+}
+
+pub async fn respond(em: EventManager, interaction: Interaction) -> InteractionResponse {
+    if matches!(interaction.kind, InteractionType::Ping) {
+        return InteractionResponse {
+            kind: InteractionResponseType::Pong,
+            data: None,
+        };
+    }
+
+    let discord_user_id = interaction.author_id().map(|id| id.to_string());
+    let Some(InteractionData::ApplicationCommand(data)) = interaction.data else {
+        return reply("That interaction isn't supported".to_owned(), true);
+    };
+
+    let db = em.db();
+    let options = &data.options;
+    let (result, ephemeral) = match data.name.as_str() {
+        "deals" => (deals(db, string_option(options, "category")).await, false),
+        "points" => (points(db, discord_user_id.as_deref()).await, true),
+        "stores" => (stores_near(db, options).await, false),
+        "link" => (
+            link(
+                db,
+                discord_user_id.as_deref(),
+                string_option(options, "code"),
+            )
+            .await,
+            true,
+        ),
+        // getting a code talks to mcdonald's, which can take longer than the 3 seconds
+        // discord waits for a response
+        "deal" => {
+            let em = em.clone();
+            let name = string_option(options, "name")
+                .unwrap_or_default()
+                .to_owned();
+            let store = string_option(options, "store")
+                .unwrap_or_default()
+                .to_owned();
+            let application_id = interaction.application_id.to_string();
+            let token = interaction.token;
+
+            tokio::spawn(async move {
+                let content = deal(&em, discord_user_id.as_deref(), &name, &store)
+                    .await
+                    .unwrap_or_else(|e| {
+                        tracing::error!("error handling /deal: {e}");
+                        "Something went wrong, try again soon".to_owned()
+                    });
+
+                let http_client = em.get_state::<ClientWithMiddleware>();
+                if let Err(e) =
+                    edit_original_response(http_client, &application_id, &token, &content).await
+                {
+                    tracing::warn!("error sending /deal response: {e}");
+                }
+            });
+
+            return InteractionResponse {
+                kind: InteractionResponseType::DeferredChannelMessageWithSource,
+                data: Some(
+                    InteractionResponseDataBuilder::new()
+                        .flags(MessageFlags::EPHEMERAL)
+                        .build(),
+                ),
+            };
+        }
+        name => (Ok(format!("Unknown command /{name}")), true),
+    };
+
+    let content = result.unwrap_or_else(|e| {
+        tracing::error!("error handling /{}: {e}", data.name);
+        "Something went wrong, try again soon".to_owned()
+    });
+
+    reply(content, ephemeral)
+}
+
+fn reply(content: String, ephemeral: bool) -> InteractionResponse {
+    let data = InteractionResponseDataBuilder::new().content(content);
+    let data = match ephemeral {
+        true => data.flags(MessageFlags::EPHEMERAL),
+        false => data,
+    };
+
+    InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(data.build()),
+    }
+}
+
+fn string_option<'a>(options: &'a [CommandDataOption], name: &str) -> Option<&'a str> {
+    options
+        .iter()
+        .find(|o| o.name == name)
+        .and_then(|o| match &o.value {
+            CommandOptionValue::String(value) => Some(value.trim()),
+            _ => None,
+        })
+}
+
+// for ILIKE, so user input can't add its own wildcards
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+// same rules as the offers query
+fn available_offers() -> Condition {
+    let locked_accounts = account_lock::Entity::find()
+        .select_only()
+        .column(account_lock::Column::Id)
+        .into_query();
+    let now = chrono::offset::Utc::now().naive_utc();
+
+    Condition::all()
+        .add(offers::Column::AccountId.not_in_subquery(locked_accounts))
+        .add(accounts::Column::Active.eq(true))
+        .add(accounts::Column::RefreshFailureCount.lte(MACCAS_ACCOUNT_REFRESH_FAILURE))
+        .add(offers::Column::ValidTo.gt(now))
+        .add(offers::Column::ValidFrom.lt(now))
+}
+
+async fn linked_user(
+    db: &DatabaseConnection,
+    discord_user_id: Option<&str>,
+) -> Result<Option<discord_users::Model>, DbErr> {
+    let Some(discord_user_id) = discord_user_id else {
+        return Ok(None);
+    };
+
+    // the roles are only trusted until they expire, after that it's as if never linked
+    let now = chrono::offset::Utc::now().naive_utc();
+    discord_users::Entity::find()
+        .filter(discord_users::Column::DiscordUserId.eq(discord_user_id))
+        .filter(discord_users::Column::RolesExpiresAt.gt(now))
+        .one(db)
+        .await
+}
+
+async fn deals(db: &DatabaseConnection, category: Option<&str>) -> Result<String, DbErr> {
+    let mut query = offers::Entity::find()
+        .select_only()
+        .column(offer_details::Column::ShortName)
+        .column_as(offer_details::Column::Price.min(), "price")
+        .join(JoinType::InnerJoin, offers::Relation::OfferDetails.def())
+        .join(JoinType::InnerJoin, offers::Relation::Accounts.def())
+        .filter(available_offers());
+
+    if let Some(category) = category {
+        query = query.filter(Expr::cust_with_values(
+            "EXISTS (SELECT 1 FROM unnest(offer_details.categories) AS category WHERE category ILIKE $1)",
+            [escape_like(category)],
+        ));
+    }
+
+    let deals = query
+        .group_by(offer_details::Column::ShortName)
+        .order_by(offer_details::Column::ShortName, Order::Asc)
+        .into_tuple::<(String, Option<f64>)>()
+        .all(db)
+        .await?;
+
+    if deals.is_empty() {
+        return Ok(match category {
+            Some(category) => format!("No {category} deals are available right now"),
+            None => "No deals are available right now".to_owned(),
+        });
+    }
+
+    let mut lines = deals
+        .iter()
+        .take(MAX_LISTED)
+        .map(|(short_name, price)| match price {
+            Some(price) => format!("- {short_name} (${price:.2})"),
+            None => format!("- {short_name}"),
+        })
+        .collect::<Vec<_>>();
+
+    if deals.len() > MAX_LISTED {
+        lines.push(format!("...and {} more", deals.len() - MAX_LISTED));
+    }
+
+    Ok(lines.join("\n"))
+}
+
+async fn deal(
+    em: &EventManager,
+    discord_user_id: Option<&str>,
+    name: &str,
+    store: &str,
+) -> Result<String, DbErr> {
+    let db = em.db();
+    let Some(user) = linked_user(db, discord_user_id).await? else {
+        return Ok(NOT_LINKED.to_owned());
+    };
+
+    // an exact name wins over a partial match that happens to expire sooner
+    let candidates = offers::Entity::find()
+        .select_only()
+        .column(offers::Column::OfferPropositionId)
+        .column(offer_details::Column::ShortName)
+        .join(JoinType::InnerJoin, offers::Relation::OfferDetails.def())
+        .join(JoinType::InnerJoin, offers::Relation::Accounts.def())
+        .filter(available_offers())
+        .filter(
+            Expr::col((offer_details::Entity, offer_details::Column::ShortName))
+                .ilike(format!("%{}%", escape_like(name))),
+        )
+        .order_by(offers::Column::ValidTo, Order::Asc)
+        .into_tuple::<(i64, String)>()
+        .all(db)
+        .await?;
+
+    let offer = candidates
+        .iter()
+        .find(|(_, short_name)| short_name.eq_ignore_ascii_case(name))
+        .or(candidates.first());
+
+    let Some((offer_proposition_id, short_name)) = offer.cloned() else {
+        return Ok(format!("No deal matching {name} is available right now"));
+    };
+
+    let store_match = stores::Entity::find()
+        .filter(
+            Condition::any()
+                .add(stores::Column::Id.eq(store))
+                .add(Expr::col(stores::Column::Name).ilike(format!("%{}%", escape_like(store)))),
+        )
+        .order_by(Expr::col(stores::Column::Id).eq(store), Order::Desc)
+        .order_by(stores::Column::Name, Order::Asc)
+        .one(db)
+        .await?;
+
+    let Some(store) = store_match else {
+        return Ok(format!("No store matching {store} was found"));
+    };
+
+    let settings = em.get_state::<Settings>();
+    let roles = serde_json::from_value::<Vec<Role>>(user.roles).unwrap_or_default();
+    let input = AddOfferInput {
+        offer_proposition_id,
+        store_id: store.id.clone(),
+        join_waitlist: false,
+    };
+
+    Ok(
+        match add_to_deal_stack(db, settings, em, Some((user.user_id, &roles)), input).await {
+            Ok(response) => format!(
+                "Your code for {short_name} at {} is **{}**",
+                store.name, response.code
+            ),
+            Err(e) => format!("Couldn't get a code for {short_name}: {}", e.message),
+        },
+    )
+}
+
+async fn points(db: &DatabaseConnection, discord_user_id: Option<&str>) -> Result<String, DbErr> {
+    let Some(user) = linked_user(db, discord_user_id).await? else {
+        return Ok(NOT_LINKED.to_owned());
+    };
+
+    let roles = serde_json::from_value::<Vec<Role>>(user.roles).unwrap_or_default();
+    if !roles.contains(&Role::Points) {
+        return Ok("Points are only available to users with the points role".to_owned());
+    }
+
+    let top = points::Entity::find()
+        .join(JoinType::InnerJoin, points::Relation::Accounts.def())
+        .filter(accounts::Column::Active.eq(true))
+        .filter(accounts::Column::RefreshFailureCount.lte(MACCAS_ACCOUNT_REFRESH_FAILURE))
+        .order_by(points::Column::CurrentPoints, Order::Desc)
+        .limit(POINTS_ACCOUNTS)
+        .all(db)
+        .await?;
+
+    if top.is_empty() {
+        return Ok("No accounts have points right now".to_owned());
+    }
+
+    Ok(top
+        .iter()
+        .map(|p| format!("- `{}` {} points", p.account_id, p.current_points))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+async fn stores_near(
+    db: &DatabaseConnection,
+    options: &[CommandDataOption],
+) -> Result<String, DbErr> {
+    let suburb = options.iter().find_map(|o| match &o.value {
+        CommandOptionValue::SubCommand(options) if o.name == "near" => {
+            string_option(options, "suburb")
+        }
+        _ => None,
+    });
+
+    let Some(suburb) = suburb else {
+        return Ok("Usage: /stores near <suburb>".to_owned());
+    };
+
+    let in_suburb = stores::Entity::find()
+        .filter(
+            Condition::any()
+                .add(Expr::col(stores::Column::Suburb).ilike(escape_like(suburb)))
+                .add(stores::Column::Postcode.eq(suburb)),
+        )
+        .all(db)
+        .await?;
+
+    let coordinates = in_suburb
+        .iter()
+        .filter_map(|s| s.latitude.zip(s.longitude))
+        .collect::<Vec<_>>();
+
+    if coordinates.is_empty() {
+        return Ok(format!("No stores found in {suburb}"));
+    }
+
+    // the suburb's own stores stand in for where it is, nearby stores can be across
+    // the border
+    let count = coordinates.len() as f64;
+    let (lat, long) = coordinates
+        .iter()
+        .fold((0.0, 0.0), |(lat, long), (a, b)| (lat + a, long + b));
+    let centre = LocationRequest {
+        lat: lat / count,
+        long: long / count,
+    };
+
+    let nearby = find_nearby_stores(db, &centre).await?;
+
+    Ok(nearby
+        .iter()
+        .take(NEARBY_STORES)
+        .map(|l| match l.distance {
+            Some(distance) => format!(
+                "- {} (`{}`) {}, {:.1}km",
+                l.model.name,
+                l.model.id,
+                l.model.address,
+                distance / 1000.0
+            ),
+            None => format!("- {} (`{}`) {}", l.model.name, l.model.id, l.model.address),
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+async fn link(
+    db: &DatabaseConnection,
+    discord_user_id: Option<&str>,
+    code: Option<&str>,
+) -> Result<String, DbErr> {
+    let (Some(discord_user_id), Some(code)) = (discord_user_id, code) else {
+        return Ok("Usage: /link <code>, create a code in the web app".to_owned());
+    };
+
+    let now = chrono::offset::Utc::now().naive_utc();
+    let Some(user) = discord_users::Entity::find()
+        .filter(discord_users::Column::LinkCode.eq(code))
+        .filter(discord_users::Column::LinkCodeExpiresAt.gt(now))
+        .one(db)
+        .await?
+    else {
+        return Ok("That link code is invalid or has expired".to_owned());
+    };
+
+    let txn = db.begin().await?;
+
+    // a discord account can only act as one user
+    discord_users::Entity::update_many()
+        .col_expr(
+            discord_users::Column::DiscordUserId,
+            Expr::value(Option::<String>::None),
+        )
+        .filter(discord_users::Column::DiscordUserId.eq(discord_user_id))
+        .exec(&txn)
+        .await?;
+
+    discord_users::Entity::update(discord_users::ActiveModel {
+        user_id: Unchanged(user.user_id),
+        discord_user_id: Set(Some(discord_user_id.to_owned())),
+        link_code: Set(None),
+        link_code_expires_at: Set(None),
+        updated_at: Set(now),
+        ..Default::default()
+    })
+    .exec(&txn)
+    .await?;
+
+    txn.commit().await?;
+
+    Ok("Linked, /deal will now get codes as you".to_owned())
+}
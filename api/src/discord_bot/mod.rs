@@ -0,0 +1,146 @@
+use crate::{
+    settings::DiscordBot,
+    types::{ApiState, AppError},
+};
+use axum::{
+    Json,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use chrono::TimeDelta;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use reqwest_middleware::ClientWithMiddleware;
+use twilight_model::{
+    application::interaction::Interaction, http::interaction::InteractionResponse,
+};
+
+mod commands;
+
+const DISCORD_API_BASE: &str = "https://discord.com/api/v10";
+const SIGNATURE_HEADER: &str = "X-Signature-Ed25519";
+const TIMESTAMP_HEADER: &str = "X-Signature-Timestamp";
+// discord sends interactions straight away, anything older is a replay
+const MAX_TIMESTAMP_AGE: TimeDelta = TimeDelta::minutes(5);
+
+#[derive(thiserror::Error, Debug)]
+pub enum DiscordBotError {
+    #[error("missing {0} header")]
+    MissingHeader(&'static str),
+    #[error("hex error: {0}")]
+    Hex(#[from] hex::FromHexError),
+    #[error("expected {0} bytes")]
+    InvalidLength(usize),
+    #[error("timestamp {0} is not recent")]
+    StaleTimestamp(String),
+    #[error("signature error: {0}")]
+    Signature(#[from] ed25519_dalek::SignatureError),
+    #[error("request error: {0}")]
+    Request(#[from] reqwest_middleware::Error),
+    #[error("discord rejected with {0}")]
+    Rejected(reqwest::StatusCode),
+}
+
+// discord sends every slash command here, it also checks the endpoint rejects
+// badly signed requests before it can be saved
+pub async fn interactions(
+    State(ApiState {
+        settings,
+        event_manager,
+        ..
+    }): State<ApiState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<InteractionResponse>, AppError> {
+    let Some(config) = &settings.discord_bot else {
+        return Err(AppError::StatusCode(StatusCode::NOT_FOUND));
+    };
+
+    if let Err(e) = verify_signature(&config.public_key, &headers, &body) {
+        tracing::warn!("rejected discord interaction: {e}");
+        return Err(AppError::StatusCode(StatusCode::UNAUTHORIZED));
+    }
+
+    let interaction = serde_json::from_slice::<Interaction>(&body)?;
+
+    Ok(Json(commands::respond(event_manager, interaction).await))
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &'static str) -> Result<&'a str, DiscordBotError> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .ok_or(DiscordBotError::MissingHeader(name))
+}
+
+// the signature covers the timestamp followed by the raw body
+fn verify_signature(
+    public_key: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<(), DiscordBotError> {
+    let signature = header(headers, SIGNATURE_HEADER)?;
+    let timestamp = header(headers, TIMESTAMP_HEADER)?;
+
+    // unix seconds, checked both ways so a timestamp from the future can't be saved for later
+    let now = chrono::offset::Utc::now().timestamp();
+    let is_recent = timestamp
+        .parse::<i64>()
+        .is_ok_and(|t| (now - t).abs() <= MAX_TIMESTAMP_AGE.num_seconds());
+    if !is_recent {
+        return Err(DiscordBotError::StaleTimestamp(timestamp.to_owned()));
+    }
+
+    let public_key = <[u8; 32]>::try_from(hex::decode(public_key)?)
+        .map_err(|_| DiscordBotError::InvalidLength(32))?;
+    let signature = <[u8; 64]>::try_from(hex::decode(signature)?)
+        .map_err(|_| DiscordBotError::InvalidLength(64))?;
+
+    let message = [timestamp.as_bytes(), body].concat();
+    VerifyingKey::from_bytes(&public_key)?.verify(&message, &Signature::from_bytes(&signature))?;
+
+    Ok(())
+}
+
+// replaces every global command, so removing one here removes it from discord
+pub async fn register_commands(
+    config: &DiscordBot,
+    http_client: &ClientWithMiddleware,
+) -> Result<(), DiscordBotError> {
+    let response = http_client
+        .put(format!(
+            "{DISCORD_API_BASE}/applications/{}/commands",
+            config.application_id
+        ))
+        .header("Authorization", format!("Bot {}", config.bot_token))
+        .json(&commands::definitions())
+        .send()
+        .await?;
+
+    match response.status() {
+        status if status.is_success() => Ok(()),
+        status => Err(DiscordBotError::Rejected(status)),
+    }
+}
+
+// deferred responses are filled in by editing the original message, the interaction
+// token is enough to authenticate
+async fn edit_original_response(
+    http_client: &ClientWithMiddleware,
+    application_id: &str,
+    interaction_token: &str,
+    content: &str,
+) -> Result<(), DiscordBotError> {
+    let response = http_client
+        .patch(format!(
+            "{DISCORD_API_BASE}/webhooks/{application_id}/{interaction_token}/messages/@original"
+        ))
+        .json(&serde_json::json!({ "content": content }))
+        .send()
+        .await?;
+
+    match response.status() {
+        status if status.is_success() => Ok(()),
+        status => Err(DiscordBotError::Rejected(status)),
+    }
+}
@@ -1,12 +1,17 @@
 use super::HandlerError;
-use crate::{event_manager::EventManager, settings::Settings};
+use crate::{
+    event_manager::EventManager,
+    offer_events::{self, DealStackAction, DealStackChange, OfferEvent, publish_for_account},
+    reservations::notify_waitlist,
+    settings::Settings,
+};
 use anyhow::Context;
 use base::constants::mc_donalds::OFFSET;
 use entity::{accounts, concurrent_active_deals, offers, sea_orm_active_enums::Action};
 use opentelemetry::trace::TraceContextExt;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue::Unchanged, ColumnTrait, EntityTrait, QueryFilter, Set,
-    TransactionTrait, prelude::Expr, sea_query::OnConflict,
+    ActiveModelTrait, ActiveValue::Unchanged, ColumnTrait, ConnectionTrait, EntityTrait,
+    QueryFilter, Set, prelude::Expr, sea_query::OnConflict,
 };
 use std::time::Duration;
 use tracing::instrument;
@@ -40,15 +45,13 @@ pub async fn cleanup(
         let proxy = reqwest::Proxy::all(settings.proxy.url.clone())?
             .basic_auth(&settings.proxy.username, &settings.proxy.password);
 
-        let account_lock_txn = db.begin().await?;
         let api_client = base::maccas::get_activated_maccas_api_client(
             account,
             proxy,
             &settings.mcdonalds.client_id,
-            &account_lock_txn,
+            db,
         )
         .await?;
-        account_lock_txn.commit().await?;
 
         let is_in_deal_stack = api_client
             .get_offers_dealstack(OFFSET, &store_id)
@@ -78,14 +81,30 @@ pub async fn cleanup(
             match response {
                 Ok(r) => {
                     tracing::info!("deal stack response: {r:?}");
-                    entity::offer_audit::ActiveModel {
-                        action: Set(Action::Remove),
-                        proposition_id: Set(offer.offer_proposition_id),
-                        transaction_id: Set(transaction_id),
-                        ..Default::default()
+
+                    // releaseOffer records its own
+                    let released = entity::offer_audit::Entity::find()
+                        .filter(entity::offer_audit::Column::TransactionId.eq(transaction_id))
+                        .filter(entity::offer_audit::Column::Action.eq(Action::Remove))
+                        .one(db)
+                        .await?
+                        .is_some();
+
+                    if !released {
+                        entity::offer_audit::ActiveModel {
+                            action: Set(Action::Remove),
+                            proposition_id: Set(offer.offer_proposition_id),
+                            user_id: Set(user_id),
+                            transaction_id: Set(transaction_id),
+                            store_id: Set(Some(store_id.clone())),
+                            ..Default::default()
+                        }
+                        .insert(db)
+                        .await?;
                     }
-                    .insert(db)
-                    .await?;
+
+                    publish_deal_stack_change(db, &offer, transaction_id, DealStackAction::Removed)
+                        .await;
                 }
                 Err(e) => tracing::error!("error checking dealstack: {}", e),
             }
@@ -103,6 +122,9 @@ pub async fn cleanup(
                 .filter(entity::offer_audit::Column::Id.eq(audit_id))
                 .exec(db)
                 .await?;
+
+            publish_deal_stack_change(db, &offer, transaction_id, DealStackAction::LikelyUsed)
+                .await;
         }
 
         if let Some(user_id) = user_id {
@@ -140,6 +162,14 @@ pub async fn cleanup(
         .exec(db)
         .await?;
 
+    if let Err(e) = publish_for_account(db, account_id, OfferEvent::added).await {
+        tracing::warn!("error publishing unlocked offers: {e}");
+    }
+
+    if let Err(e) = notify_waitlist(db, account_id).await {
+        tracing::warn!("error notifying waitlist: {e}");
+    }
+
     let trace_id = opentelemetry::Context::current()
         .span()
         .span_context()
@@ -156,3 +186,20 @@ pub async fn cleanup(
 
     result
 }
+
+async fn publish_deal_stack_change(
+    db: &impl ConnectionTrait,
+    offer: &offers::Model,
+    transaction_id: Uuid,
+    action: DealStackAction,
+) {
+    let event = OfferEvent::DealStackChanged(DealStackChange {
+        transaction_id,
+        offer_proposition_id: offer.offer_proposition_id,
+        action,
+    });
+
+    if let Err(e) = offer_events::publish(db, &event).await {
+        tracing::warn!("error publishing deal stack change: {e}");
+    }
+}
@@ -0,0 +1,18 @@
+use super::HandlerError;
+use crate::{event_manager::EventManager, notification_channels};
+use serde_json::Value;
+use tracing::instrument;
+
+#[instrument(skip(body, em))]
+pub async fn deliver_notification(
+    channel: String,
+    target: String,
+    offer_proposition_id: i64,
+    body: Value,
+    em: EventManager,
+) -> Result<(), HandlerError> {
+    notification_channels::deliver_deferred(&em, &channel, &target, offer_proposition_id, body)
+        .await?;
+
+    Ok(())
+}
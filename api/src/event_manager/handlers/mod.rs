@@ -11,23 +11,29 @@ use base::{
     retry::{ExponentialBackoff, RetryResult, retry_async},
 };
 use converters::ConversionError;
+use deliver_notification::deliver_notification;
 use futures::FutureExt;
 use new_offer_found::new_offer_found;
+use offer_expiring::offer_expiring;
 use populate_offer_details_cache::{
     populate_offer_details_cache, populate_offer_details_cache_for,
 };
 use refresh_account::refresh_account;
 use refresh_points::refresh_points;
+use retry_notification_delivery::retry_notification_delivery;
 use sea_orm::DbErr;
 use std::{fmt::Display, num::TryFromIntError, panic::AssertUnwindSafe, time::Duration};
 use thiserror::Error;
 use tracing::{Instrument, span};
 
 mod cleanup;
+mod deliver_notification;
 mod new_offer_found;
+mod offer_expiring;
 mod populate_offer_details_cache;
 mod refresh_account;
 mod refresh_points;
+mod retry_notification_delivery;
 mod save_image;
 
 pub use save_image::S3BucketType;
@@ -149,6 +155,27 @@ pub async fn handle(event_manager: EventManager) {
                     Event::NewOfferFound {
                         offer_proposition_id,
                     } => new_offer_found(offer_proposition_id, event_manager).await,
+                    Event::OfferExpiring {
+                        offer_proposition_id,
+                    } => offer_expiring(offer_proposition_id, event_manager).await,
+                    Event::RetryNotificationDelivery { delivery_id } => {
+                        retry_notification_delivery(delivery_id, event_manager).await
+                    }
+                    Event::DeliverNotification {
+                        channel,
+                        target,
+                        offer_proposition_id,
+                        body,
+                    } => {
+                        deliver_notification(
+                            channel,
+                            target,
+                            offer_proposition_id,
+                            body,
+                            event_manager,
+                        )
+                        .await
+                    }
                     event => {
                         let name = event.name();
                         if let Some(name) = name {
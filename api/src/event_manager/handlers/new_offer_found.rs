@@ -1,28 +1,32 @@
 use super::HandlerError;
 use crate::{
-    discord_webhook::DiscordWebhookMessage, event_manager::EventManager, settings::Settings,
+    event_manager::EventManager,
+    notification_channels::{self, NewOfferNotification},
+    push_notifications::{PushNotification, WebPushSender},
+    settings::{self, Settings},
 };
 use anyhow::Context;
+use api::Event;
 use base::{
     constants::{IMAGE_BASE_URL, IMAGE_EXT},
     http::get_http_client,
     jwt::generate_internal_jwt,
 };
-use entity::{offer_details, offers};
+use chrono::TimeDelta;
+use entity::{
+    notification_rules, offer_cluster_score, offer_details, offer_name_cluster_association, offers,
+};
+use opentelemetry::trace::TraceContextExt;
 use recommendations::GenerateEmbeddingsFor;
 use reqwest::StatusCode;
-use reqwest::header::CONTENT_TYPE;
 use reqwest_middleware::ClientWithMiddleware;
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, JoinType, QueryFilter, QueryOrder,
+    QuerySelect,
+    prelude::{DateTime, Uuid},
+};
+use std::collections::HashSet;
 use tracing::instrument;
-use twilight_model::util::Timestamp;
-use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, ImageSource};
-
-#[derive(serde::Serialize)]
-struct ExternalUrlPayload {
-    details: offer_details::Model,
-    offer: offers::Model,
-}
 
 #[instrument(skip(em))]
 pub async fn new_offer_found(
@@ -71,61 +75,31 @@ pub async fn new_offer_found(
         tracing::error!("failed to produce embedding {e}");
     }
 
-    let should_notify = config.should_notify_discord() || config.should_notify_external();
-    if !should_notify {
-        tracing::warn!("notification disabled or no urls configured");
-        return Ok(());
-    }
-
     let db = em.db();
     let details = offer_details::Entity::find_by_id(offer_proposition_id)
         .limit(1)
         .one(db)
         .await?;
 
-    if details.is_none() {
+    let Some(details) = details else {
         tracing::warn!("details not found for {offer_proposition_id}");
-    }
-
-    let details = details.unwrap();
-
-    let embed = EmbedBuilder::new()
-        .color(0xDA291C)
-        .title("New Deal")
-        .field(EmbedFieldBuilder::new("Name", &details.short_name))
-        .timestamp(
-            Timestamp::from_secs(details.created_at.and_utc().timestamp())
-                .context("must have valid time")
-                .unwrap(),
-        );
+        return Ok(());
+    };
 
-    let image = ImageSource::url(format!(
-        "{IMAGE_BASE_URL}/{}.{IMAGE_EXT}",
-        details.image_base_name
-    ));
+    if let Some(push_config) = &settings.web_push {
+        if let Err(e) = notify_push_subscribers(&em, push_config, &details).await {
+            tracing::warn!("error sending new offer push notifications: {e}");
+        }
 
-    let embed = match image {
-        Ok(image) => embed.thumbnail(image),
-        Err(_) => embed,
+        if let Err(e) = schedule_expiry_warning(&em, push_config, offer_proposition_id).await {
+            tracing::warn!("error scheduling expiry warning: {e}");
+        }
     }
-    .build();
-
-    let mut webhook_message =
-        DiscordWebhookMessage::new("Maccas".to_owned(), format!("{IMAGE_BASE_URL}/og.png"));
-    let webhook_message = webhook_message.add_embed(embed);
-
-    let http_client = em.get_state::<ClientWithMiddleware>();
-
-    for discord_url in &config.discord_urls {
-        if let Err(e) = http_client
-            .post(discord_url.as_str())
-            .header(CONTENT_TYPE, "application/json")
-            .json(webhook_message)
-            .send()
-            .await
-        {
-            tracing::warn!("error in discord webhook: {e}");
-        };
+
+    let channels = notification_channels::from_settings(config, &settings.external_webhook_secret);
+    if channels.is_empty() {
+        tracing::warn!("notification disabled or no channels configured");
+        return Ok(());
     }
 
     let example_offer = offers::Entity::find()
@@ -136,26 +110,145 @@ pub async fn new_offer_found(
         .await?
         .context("no matching offer found")?;
 
-    let external_url_payload = ExternalUrlPayload {
+    let notification = NewOfferNotification {
         details,
         offer: example_offer,
     };
 
-    for external_url in &config.external_urls {
-        if let Err(e) = http_client
-            .post(external_url.as_str())
-            .header(CONTENT_TYPE, "application/json")
-            .header(
-                "X-Maccas-External-Secret",
-                &settings.external_webhook_secret,
+    // channels are rate limited independently, one busy target shouldn't hold up the rest.
+    // failed sends are retried by their own event, so this event must not fail and resend
+    let deliveries = channels
+        .iter()
+        .map(|channel| notification_channels::deliver(&em, channel.as_ref(), &notification));
+    for result in futures::future::join_all(deliveries).await {
+        if let Err(e) = result {
+            tracing::warn!("error recording notification delivery: {e}");
+        }
+    }
+
+    Ok(())
+}
+
+// cluster scores below this come from a single old redemption, same cutoff as recommendations
+const MIN_CLUSTER_SCORE: f64 = 1.0;
+// offers the clustering couldn't place
+const NOISE_CLUSTER_ID: i64 = -1;
+
+async fn matching_users(
+    db: &DatabaseConnection,
+    details: &offer_details::Model,
+) -> Result<HashSet<Uuid>, DbErr> {
+    let rules = notification_rules::Entity::find().all(db).await?;
+
+    let categories = details.categories.clone().unwrap_or_default();
+    let text = format!("{} {}", details.name, details.description).to_lowercase();
+
+    let similar_users = if rules.iter().any(|r| r.similar_to_used) {
+        offer_cluster_score::Entity::find()
+            .select_only()
+            .column(offer_cluster_score::Column::UserId)
+            .join(
+                JoinType::InnerJoin,
+                offer_cluster_score::Entity::belongs_to(offer_name_cluster_association::Entity)
+                    .from(offer_cluster_score::Column::ClusterId)
+                    .to(offer_name_cluster_association::Column::ClusterId)
+                    .into(),
             )
-            .json(&external_url_payload)
-            .send()
-            .await
-        {
-            tracing::warn!("error in external url: {e}");
-        };
+            .filter(offer_name_cluster_association::Column::Name.eq(&details.short_name))
+            .filter(offer_cluster_score::Column::ClusterId.ne(NOISE_CLUSTER_ID))
+            .filter(offer_cluster_score::Column::Score.gte(MIN_CLUSTER_SCORE))
+            .into_tuple::<Uuid>()
+            .all(db)
+            .await?
+            .into_iter()
+            .collect::<HashSet<_>>()
+    } else {
+        HashSet::new()
+    };
+
+    Ok(rules
+        .into_iter()
+        .filter(|rule| {
+            rule.categories.iter().any(|c| categories.contains(c))
+                || rule
+                    .keywords
+                    .iter()
+                    .any(|k| text.contains(&k.to_lowercase()))
+                || (rule.similar_to_used && similar_users.contains(&rule.user_id))
+        })
+        .map(|rule| rule.user_id)
+        .collect())
+}
+
+async fn notify_push_subscribers(
+    em: &EventManager,
+    config: &settings::WebPush,
+    details: &offer_details::Model,
+) -> Result<(), HandlerError> {
+    let db = em.db();
+    let user_ids = matching_users(db, details).await?;
+    if user_ids.is_empty() {
+        return Ok(());
     }
 
+    let http_client = em.get_state::<ClientWithMiddleware>().clone();
+    let sender = WebPushSender::new(config, http_client).map_err(anyhow::Error::from)?;
+
+    let notification = PushNotification {
+        title: "New Deal".to_owned(),
+        body: details.short_name.clone(),
+        tag: format!("new-offer-{}", details.proposition_id),
+        image: Some(format!(
+            "{IMAGE_BASE_URL}/{}.{IMAGE_EXT}",
+            details.image_base_name
+        )),
+        url: None,
+    };
+
+    let delivered = sender.send_to_users(db, user_ids, &notification).await?;
+    tracing::info!("new offer push delivered to {delivered} subscriptions");
+
+    Ok(())
+}
+
+// OfferExpiring checks valid_to again when it runs, so this only needs to be roughly right
+async fn schedule_expiry_warning(
+    em: &EventManager,
+    config: &settings::WebPush,
+    offer_proposition_id: i64,
+) -> Result<(), HandlerError> {
+    let valid_to = offers::Entity::find()
+        .select_only()
+        .column_as(offers::Column::ValidTo.max(), "valid_to")
+        .filter(offers::Column::OfferPropositionId.eq(offer_proposition_id))
+        .into_tuple::<Option<DateTime>>()
+        .one(em.db())
+        .await?
+        .flatten();
+
+    let Some(valid_to) = valid_to else {
+        return Ok(());
+    };
+
+    let warn_at = valid_to - TimeDelta::minutes(config.expiry_warning_minutes);
+    let delay = (warn_at - chrono::offset::Utc::now().naive_utc())
+        .to_std()
+        .unwrap_or_default();
+
+    let trace_id = opentelemetry::Context::current()
+        .span()
+        .span_context()
+        .trace_id()
+        .to_string();
+
+    em.create_event(
+        Event::OfferExpiring {
+            offer_proposition_id,
+        },
+        delay,
+        trace_id,
+    )
+    .await?;
+
     Ok(())
 }
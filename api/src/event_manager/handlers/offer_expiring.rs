@@ -0,0 +1,114 @@
+use super::HandlerError;
+use crate::{
+    event_manager::EventManager,
+    push_notifications::{PushNotification, WebPushSender},
+    settings::Settings,
+};
+use api::Event;
+use base::constants::{IMAGE_BASE_URL, IMAGE_EXT};
+use chrono::TimeDelta;
+use entity::{offer_details, offer_favourites, offers};
+use opentelemetry::trace::TraceContextExt;
+use reqwest_middleware::ClientWithMiddleware;
+use sea_orm::{
+    ColumnTrait, EntityTrait, QueryFilter, QuerySelect,
+    prelude::{DateTime, Uuid},
+};
+use tracing::instrument;
+
+#[instrument(skip(em))]
+pub async fn offer_expiring(
+    offer_proposition_id: i64,
+    em: EventManager,
+) -> Result<(), HandlerError> {
+    let settings = em.get_state::<Settings>();
+    let Some(config) = &settings.web_push else {
+        tracing::info!("web push not configured");
+        return Ok(());
+    };
+
+    let db = em.db();
+    let Some(details) = offer_details::Entity::find_by_id(offer_proposition_id)
+        .one(db)
+        .await?
+    else {
+        tracing::warn!("details not found for {offer_proposition_id}");
+        return Ok(());
+    };
+
+    // refreshes can move valid_to after this was scheduled
+    let now = chrono::offset::Utc::now().naive_utc();
+    let valid_to = offers::Entity::find()
+        .select_only()
+        .column_as(offers::Column::ValidTo.max(), "valid_to")
+        .filter(offers::Column::OfferPropositionId.eq(offer_proposition_id))
+        .filter(offers::Column::ValidTo.gt(now))
+        .into_tuple::<Option<DateTime>>()
+        .one(db)
+        .await?
+        .flatten();
+
+    let Some(valid_to) = valid_to else {
+        tracing::info!("{offer_proposition_id} is no longer available");
+        return Ok(());
+    };
+
+    let warning = TimeDelta::minutes(config.expiry_warning_minutes);
+    if valid_to - now > warning {
+        let delay = (valid_to - warning - now).to_std().unwrap_or_default();
+        tracing::info!("{offer_proposition_id} was extended, warning again in {delay:?}");
+
+        let trace_id = opentelemetry::Context::current()
+            .span()
+            .span_context()
+            .trace_id()
+            .to_string();
+
+        em.create_event(
+            Event::OfferExpiring {
+                offer_proposition_id,
+            },
+            delay,
+            trace_id,
+        )
+        .await?;
+
+        return Ok(());
+    }
+
+    let user_ids = offer_favourites::Entity::find()
+        .select_only()
+        .column(offer_favourites::Column::UserId)
+        .filter(offer_favourites::Column::ShortName.eq(&details.short_name))
+        .into_tuple::<Uuid>()
+        .all(db)
+        .await?;
+
+    if user_ids.is_empty() {
+        return Ok(());
+    }
+
+    let remaining = match (valid_to - now).num_hours() {
+        0 => "less than an hour".to_owned(),
+        1 => "1 hour".to_owned(),
+        hours => format!("{hours} hours"),
+    };
+
+    let notification = PushNotification {
+        title: "Deal Expiring".to_owned(),
+        body: format!("{} expires in {remaining}", details.short_name),
+        tag: format!("offer-expiring-{offer_proposition_id}"),
+        image: Some(format!(
+            "{IMAGE_BASE_URL}/{}.{IMAGE_EXT}",
+            details.image_base_name
+        )),
+        url: None,
+    };
+
+    let http_client = em.get_state::<ClientWithMiddleware>().clone();
+    let sender = WebPushSender::new(config, http_client).map_err(anyhow::Error::from)?;
+    let delivered = sender.send_to_users(db, user_ids, &notification).await?;
+    tracing::info!("expiry warning delivered to {delivered} subscriptions");
+
+    Ok(())
+}
@@ -2,6 +2,7 @@ use super::HandlerError;
 use crate::caching::OfferDetailsCache;
 use crate::{event_manager::EventManager, jobs::shared, settings::Settings};
 use anyhow::Context;
+use api::Event;
 use base::http::get_proxied_maccas_http_client;
 use entity::accounts;
 use opentelemetry::trace::TraceContextExt;
@@ -28,7 +29,7 @@ pub async fn refresh_account(account_id: Uuid, em: EventManager) -> Result<(), H
     let http_client = get_proxied_maccas_http_client(proxy)?;
 
     let caching = em.try_get_state::<OfferDetailsCache>();
-    let events_to_dispatch = shared::refresh_account(
+    let result = shared::refresh_account(
         account,
         &http_client,
         &settings.mcdonalds,
@@ -37,9 +38,7 @@ pub async fn refresh_account(account_id: Uuid, em: EventManager) -> Result<(), H
         caching,
         CancellationToken::new(),
     )
-    .await?;
-
-    db.commit().await?;
+    .await;
 
     let trace_id = opentelemetry::Context::current()
         .span()
@@ -47,6 +46,21 @@ pub async fn refresh_account(account_id: Uuid, em: EventManager) -> Result<(), H
         .trace_id()
         .to_string();
 
+    let events_to_dispatch = match result {
+        Ok(events) => events,
+        Err(e) => match shared::rate_limited_for(&e) {
+            Some(backoff) => {
+                tracing::warn!("rate limited, refreshing {account_id} again in {backoff:?}");
+                em.create_event(Event::RefreshAccount { account_id }, backoff, trace_id)
+                    .await?;
+                return Ok(());
+            }
+            None => return Err(e.into()),
+        },
+    };
+
+    db.commit().await?;
+
     for event in events_to_dispatch {
         em.create_event(event, Duration::from_secs(30), trace_id.clone())
             .await?;
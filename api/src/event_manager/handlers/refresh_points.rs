@@ -21,15 +21,13 @@ pub async fn refresh_points(account_id: Uuid, em: EventManager) -> Result<(), Ha
     let proxy = reqwest::Proxy::all(settings.proxy.url.clone())?
         .basic_auth(&settings.proxy.username, &settings.proxy.password);
 
-    let account_lock_txn = db.begin().await?;
     let api_client = base::maccas::get_activated_maccas_api_client(
         account,
         proxy,
         &settings.mcdonalds.client_id,
-        &account_lock_txn,
+        db,
     )
     .await?;
-    account_lock_txn.commit().await?;
 
     let txn = db.begin().await?;
 
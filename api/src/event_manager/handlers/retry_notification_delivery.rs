@@ -0,0 +1,13 @@
+use super::HandlerError;
+use crate::{event_manager::EventManager, notification_channels};
+use tracing::instrument;
+
+#[instrument(skip(em))]
+pub async fn retry_notification_delivery(
+    delivery_id: i32,
+    em: EventManager,
+) -> Result<(), HandlerError> {
+    notification_channels::redeliver(&em, delivery_id).await?;
+
+    Ok(())
+}
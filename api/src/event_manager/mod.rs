@@ -172,7 +172,8 @@ impl EventManager {
             duplicate_events.rows_affected
         );
 
-        self.inner
+        let message_id = self
+            .inner
             .event_queue
             .push(
                 QueuedEvent {
@@ -184,9 +185,50 @@ impl EventManager {
             )
             .await?;
 
+        events::ActiveModel {
+            id: Unchanged(event.id),
+            message_id: Set(Some(message_id)),
+            ..Default::default()
+        }
+        .update(&self.inner.db)
+        .await?;
+
         Ok(event_id)
     }
 
+    // only pending events can be moved, returns false when it already ran or was replaced
+    #[instrument(skip(self))]
+    pub async fn reschedule_event(
+        &self,
+        id: i32,
+        delay: Duration,
+    ) -> Result<bool, EventManagerError> {
+        let Some(event) = events::Entity::find_by_id(id).one(&self.inner.db).await? else {
+            return Ok(false);
+        };
+
+        let Some(message_id) = event.message_id else {
+            return Ok(false);
+        };
+
+        if event.status != EventStatus::Pending {
+            return Ok(false);
+        }
+
+        let should_be_completed_at = chrono::offset::Utc::now().naive_utc() + delay;
+        events::ActiveModel {
+            id: Unchanged(id),
+            should_be_completed_at: Set(should_be_completed_at),
+            ..Default::default()
+        }
+        .update(&self.inner.db)
+        .await?;
+
+        self.inner.event_queue.reschedule(message_id, delay).await?;
+
+        Ok(true)
+    }
+
     pub async fn should_run(&self, event_id: i32) -> bool {
         let Some(event) = events::Entity::find_by_id(event_id)
             .one(&self.inner.db)
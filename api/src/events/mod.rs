@@ -12,12 +12,17 @@ pub enum Event {
     CategoriseOffers {},
     CreateAccount {},
     GenerateRecommendations {},
+    PruneWaitlist {},
     RecategoriseOffers {},
+    ReconcileActiveDeals {},
     Refresh {},
+    RefreshMenuCatalog {},
+    RefreshUserStats {},
     RefreshAccount {
         account_id: Uuid,
     },
     SaveImages {},
+    SyncStores {},
     Cleanup {
         offer_id: Uuid,
         transaction_id: Uuid,
@@ -37,6 +42,18 @@ pub enum Event {
     NewOfferFound {
         offer_proposition_id: i64,
     },
+    OfferExpiring {
+        offer_proposition_id: i64,
+    },
+    RetryNotificationDelivery {
+        delivery_id: i32,
+    },
+    DeliverNotification {
+        channel: String,
+        target: String,
+        offer_proposition_id: i64,
+        body: serde_json::Value,
+    },
     PopulateOfferDetailsCache,
     PopulateOfferDetailsCacheFor {
         offer_proposition_id: i64,
@@ -52,9 +69,14 @@ impl MaybeJobScheduler for Event {
             Event::CategoriseOffers {} => Some("categorise_offers"),
             Event::CreateAccount {} => Some("create_account"),
             Event::GenerateRecommendations {} => Some("generate_recommendations"),
+            Event::PruneWaitlist {} => Some("prune_waitlist"),
             Event::RecategoriseOffers {} => Some("recategorise_offers"),
+            Event::ReconcileActiveDeals {} => Some("reconcile_active_deals"),
             Event::Refresh {} => Some("refresh"),
+            Event::RefreshMenuCatalog {} => Some("refresh_menu_catalog"),
+            Event::RefreshUserStats {} => Some("refresh_user_stats"),
             Event::SaveImages {} => Some("save_images"),
+            Event::SyncStores {} => Some("sync_stores"),
             _ => None,
         }
     }
@@ -71,15 +93,23 @@ impl fmt::Display for Event {
             Event::SaveImage { .. } => write!(f, "SaveImage"),
             Event::RefreshPoints { .. } => write!(f, "RefreshPoints"),
             Event::NewOfferFound { .. } => write!(f, "NewOfferFound"),
+            Event::OfferExpiring { .. } => write!(f, "OfferExpiring"),
+            Event::RetryNotificationDelivery { .. } => write!(f, "RetryNotificationDelivery"),
+            Event::DeliverNotification { .. } => write!(f, "DeliverNotification"),
             Event::UnlockAllAccounts {} => write!(f, "UnlockAllAccounts "),
             Event::ActivateAccount {} => write!(f, "ActivateAccount"),
             Event::ActivateExistingAccount {} => write!(f, "ActivateExistingAccount"),
             Event::CategoriseOffers {} => write!(f, "CategoriseOffers"),
             Event::CreateAccount {} => write!(f, "CreateAccount"),
             Event::GenerateRecommendations {} => write!(f, "GenerateRecommendations"),
+            Event::PruneWaitlist {} => write!(f, "PruneWaitlist"),
             Event::RecategoriseOffers {} => write!(f, "RecategoriseOffers"),
+            Event::ReconcileActiveDeals {} => write!(f, "ReconcileActiveDeals"),
             Event::Refresh {} => write!(f, "Refresh"),
+            Event::RefreshMenuCatalog {} => write!(f, "RefreshMenuCatalog"),
+            Event::RefreshUserStats {} => write!(f, "RefreshUserStats"),
             Event::SaveImages {} => write!(f, "SaveImages"),
+            Event::SyncStores {} => write!(f, "SyncStores"),
             Event::RefreshAccount { .. } => write!(f, "RefreshAccount"),
             Event::PopulateOfferDetailsCache => write!(f, "PopulateOfferDetailsCache"),
             Event::PopulateOfferDetailsCacheFor { .. } => write!(f, "PopulateOfferDetailsCacheFor"),
@@ -122,6 +152,11 @@ pub struct GetEventsHistoryResponse {
     pub historical_events: Vec<entity::events::Model>,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct GetNotificationDeliveriesResponse {
+    pub deliveries: Vec<entity::notification_deliveries::Model>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
 pub struct GetEventsResponse {
     pub events: Vec<String>,
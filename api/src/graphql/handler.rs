@@ -1,14 +1,28 @@
 use crate::types::{ApiState, AppError};
 use anyhow::Context;
-use async_graphql::{ServerError, http::GraphiQLSource};
-use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use async_graphql::{
+    Data, ServerError,
+    http::{ALL_WEBSOCKET_PROTOCOLS, GraphiQLSource},
+};
+use async_graphql_axum::{GraphQLProtocol, GraphQLRequest, GraphQLResponse, GraphQLWebSocket};
 use axum::http::StatusCode;
 use axum::response::Result;
-use axum::{Json, extract::State, http::HeaderMap, response::IntoResponse};
+use axum::{
+    Json,
+    extract::{State, WebSocketUpgrade},
+    http::HeaderMap,
+    response::IntoResponse,
+};
 use base::jwt::{self, JwtClaims};
 
 pub async fn graphiql() -> impl IntoResponse {
-    axum::response::Html(GraphiQLSource::build().endpoint("/v1/graphql").finish()).into_response()
+    axum::response::Html(
+        GraphiQLSource::build()
+            .endpoint("/v1/graphql")
+            .subscription_endpoint("/v1/graphql/ws")
+            .finish(),
+    )
+    .into_response()
 }
 
 #[allow(unused)]
@@ -81,6 +95,48 @@ pub async fn graphql_handler(
     }
 }
 
+pub async fn graphql_ws_handler(
+    State(ApiState {
+        schema, settings, ..
+    }): State<ApiState>,
+    protocol: GraphQLProtocol,
+    upgrade: WebSocketUpgrade,
+) -> impl IntoResponse {
+    upgrade
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |stream| {
+            GraphQLWebSocket::new(stream, schema, protocol)
+                .on_connection_init(move |payload| {
+                    on_connection_init(settings.auth_secret.clone(), payload)
+                })
+                .serve()
+        })
+}
+
+// websockets can't carry the Authorization header, clients send it in connection_init instead
+async fn on_connection_init(
+    auth_secret: String,
+    payload: serde_json::Value,
+) -> async_graphql::Result<Data> {
+    let token = payload
+        .get("Authorization")
+        .and_then(|v| v.as_str())
+        .map(|v| v.replace("Bearer ", ""));
+
+    let mut data = Data::default();
+    match token {
+        Some(token) => {
+            let claims = jwt::verify_jwt(auth_secret.as_bytes(), &token)?;
+            data.insert(ValidatedToken(token));
+            data.insert(ValidatedClaims(claims));
+        }
+        None if cfg!(debug_assertions) => {}
+        None => return Err("Unauthorized request".into()),
+    }
+
+    Ok(data)
+}
+
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct HealthResponse {
     recommendations: bool,
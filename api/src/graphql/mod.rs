@@ -1,15 +1,21 @@
 use self::{
-    mutations::offers::OffersMutation,
+    mutations::{
+        accounts::AccountsMutation, discord::DiscordMutation, notifications::NotificationsMutation,
+        offers::OffersMutation,
+    },
     queries::{
-        categories::CategoryQuery, health::HealthQuery, locations::LocationsQuery,
-        offers::OffersQuery, points::PointsQuery,
+        accounts::AccountsQuery, categories::CategoryQuery, health::HealthQuery,
+        history::HistoryQuery, locations::LocationsQuery, notifications::NotificationsQuery,
+        offers::OffersQuery, points::PointsQuery, products::ProductsQuery, stats::StatsQuery,
     },
+    subscriptions::SubscriptionRoot,
 };
-use async_graphql::{EmptySubscription, MergedObject, Schema};
+use async_graphql::{MergedObject, Schema};
 
 mod handler;
 pub mod mutations;
 pub mod queries;
+pub mod subscriptions;
 pub mod tracing;
 pub use handler::*;
 pub mod guard;
@@ -21,9 +27,19 @@ pub struct QueryRoot(
     PointsQuery,
     LocationsQuery,
     CategoryQuery,
+    ProductsQuery,
+    HistoryQuery,
+    StatsQuery,
+    NotificationsQuery,
+    AccountsQuery,
 );
 
 #[derive(Default, MergedObject)]
-pub struct MutationRoot(OffersMutation);
+pub struct MutationRoot(
+    OffersMutation,
+    NotificationsMutation,
+    DiscordMutation,
+    AccountsMutation,
+);
 
-pub type FinalSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+pub type FinalSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
@@ -0,0 +1,92 @@
+use crate::{event_manager::EventManager, graphql::guard::RoleGuard};
+use api::Event;
+use async_graphql::{Context, Object};
+use base::jwt::Role;
+use entity::accounts;
+use opentelemetry::trace::TraceContextExt;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, prelude::Uuid};
+use std::time::Duration;
+
+#[derive(Default)]
+pub struct AccountsMutation;
+
+#[Object]
+impl AccountsMutation {
+    // disabled accounts are skipped by the jobs and their offers and points aren't handed
+    // out, but both are kept for when the account is enabled again
+    #[graphql(guard = "RoleGuard::with_role(Role::Admin)")]
+    async fn set_account_active(
+        &self,
+        ctx: &Context<'_>,
+        account_id: Uuid,
+        active: bool,
+    ) -> async_graphql::Result<bool> {
+        let db = ctx.data::<DatabaseConnection>()?;
+
+        let res = accounts::Entity::update_many()
+            .set(accounts::ActiveModel {
+                active: Set(active),
+                ..Default::default()
+            })
+            .filter(accounts::Column::Id.eq(account_id))
+            .exec(db)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+
+    // puts a failing account back in the pool, the next failed refresh counts from zero
+    #[graphql(guard = "RoleGuard::with_role(Role::Admin)")]
+    async fn reset_account_failures(
+        &self,
+        ctx: &Context<'_>,
+        account_id: Uuid,
+    ) -> async_graphql::Result<bool> {
+        let db = ctx.data::<DatabaseConnection>()?;
+
+        let res = accounts::Entity::update_many()
+            .set(accounts::ActiveModel {
+                refresh_failure_count: Set(0),
+                ..Default::default()
+            })
+            .filter(accounts::Column::Id.eq(account_id))
+            .exec(db)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+
+    #[graphql(guard = "RoleGuard::with_role(Role::Admin)")]
+    async fn refresh_account(
+        &self,
+        ctx: &Context<'_>,
+        account_id: Uuid,
+    ) -> async_graphql::Result<bool> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let event_manager = ctx.data::<EventManager>()?;
+
+        if accounts::Entity::find_by_id(account_id)
+            .one(db)
+            .await?
+            .is_none()
+        {
+            return Ok(false);
+        }
+
+        let trace_id = opentelemetry::Context::current()
+            .span()
+            .span_context()
+            .trace_id()
+            .to_string();
+
+        event_manager
+            .create_event(
+                Event::RefreshAccount { account_id },
+                Duration::ZERO,
+                trace_id,
+            )
+            .await?;
+
+        Ok(true)
+    }
+}
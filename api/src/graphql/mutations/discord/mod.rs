@@ -0,0 +1,73 @@
+use crate::graphql::ValidatedClaims;
+use anyhow::Context as _;
+use async_graphql::{Context, Object};
+use chrono::TimeDelta;
+use entity::discord_users;
+use rand::{
+    SeedableRng,
+    distr::{Alphanumeric, SampleString},
+    rngs::{StdRng, SysRng},
+};
+use sea_orm::{
+    DatabaseConnection, EntityTrait, Set,
+    prelude::{Expr, Uuid},
+    sea_query::OnConflict,
+};
+
+const LINK_CODE_LENGTH: usize = 10;
+const LINK_CODE_LIFETIME: TimeDelta = TimeDelta::minutes(10);
+// the copied roles aren't updated when they change, so a link only lasts this long
+const ROLES_LIFETIME: TimeDelta = TimeDelta::days(7);
+
+#[derive(Default)]
+pub struct DiscordMutation;
+
+#[Object]
+impl DiscordMutation {
+    // redeemed with /link in discord, roles are copied from this token and expire after
+    // ROLES_LIFETIME, a user with new roles needs to link again
+    async fn create_discord_link_code(&self, ctx: &Context<'_>) -> async_graphql::Result<String> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let claims = &ctx.data::<ValidatedClaims>()?.0;
+        let user_id = claims.user_id.parse::<Uuid>()?;
+
+        let mut rng = StdRng::try_from_rng(&mut SysRng).context("must create rng")?;
+        let link_code = Alphanumeric.sample_string(&mut rng, LINK_CODE_LENGTH);
+        let now = chrono::offset::Utc::now().naive_utc();
+
+        discord_users::Entity::insert(discord_users::ActiveModel {
+            user_id: Set(user_id),
+            roles: Set(serde_json::to_value(&claims.role)?),
+            roles_expires_at: Set(now + ROLES_LIFETIME),
+            link_code: Set(Some(link_code.clone())),
+            link_code_expires_at: Set(Some(now + LINK_CODE_LIFETIME)),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::column(discord_users::Column::UserId)
+                .update_columns([
+                    discord_users::Column::Roles,
+                    discord_users::Column::RolesExpiresAt,
+                    discord_users::Column::LinkCode,
+                    discord_users::Column::LinkCodeExpiresAt,
+                ])
+                .value(discord_users::Column::UpdatedAt, Expr::current_timestamp())
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+        Ok(link_code)
+    }
+
+    async fn unlink_discord(&self, ctx: &Context<'_>) -> async_graphql::Result<bool> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+
+        let res = discord_users::Entity::delete_by_id(user_id)
+            .exec(db)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+}
@@ -1 +1,4 @@
+pub mod accounts;
+pub mod discord;
+pub mod notifications;
 pub mod offers;
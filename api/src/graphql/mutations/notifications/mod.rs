@@ -0,0 +1,164 @@
+use self::types::{
+    AddNotificationRuleInput, FavouriteOfferInput, SubscribePushInput, UnsubscribePushInput,
+};
+use crate::{
+    graphql::{ValidatedClaims, queries::notifications::types::NotificationRule},
+    push_notifications::validate_subscription,
+};
+use async_graphql::{Context, Object};
+use entity::{notification_rules, offer_favourites, push_subscriptions};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    prelude::Uuid, sea_query::OnConflict,
+};
+
+mod types;
+
+#[derive(Default)]
+pub struct NotificationsMutation;
+
+#[Object]
+impl NotificationsMutation {
+    async fn subscribe_push(
+        &self,
+        ctx: &Context<'_>,
+        input: SubscribePushInput,
+    ) -> async_graphql::Result<bool> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+
+        validate_subscription(&input.endpoint, &input.p256dh, &input.auth)?;
+
+        // a browser keeps its endpoint across logins, so it follows whoever subscribed last
+        push_subscriptions::Entity::insert(push_subscriptions::ActiveModel {
+            user_id: Set(user_id),
+            endpoint: Set(input.endpoint),
+            p256dh: Set(input.p256dh),
+            auth: Set(input.auth),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::column(push_subscriptions::Column::Endpoint)
+                .update_columns([
+                    push_subscriptions::Column::UserId,
+                    push_subscriptions::Column::P256dh,
+                    push_subscriptions::Column::Auth,
+                ])
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+        Ok(true)
+    }
+
+    async fn unsubscribe_push(
+        &self,
+        ctx: &Context<'_>,
+        input: UnsubscribePushInput,
+    ) -> async_graphql::Result<bool> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+
+        let res = push_subscriptions::Entity::delete_many()
+            .filter(push_subscriptions::Column::UserId.eq(user_id))
+            .filter(push_subscriptions::Column::Endpoint.eq(input.endpoint))
+            .exec(db)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+
+    async fn add_notification_rule(
+        &self,
+        ctx: &Context<'_>,
+        input: AddNotificationRuleInput,
+    ) -> async_graphql::Result<NotificationRule> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+
+        let keywords = input
+            .keywords
+            .into_iter()
+            .map(|k| k.trim().to_owned())
+            .filter(|k| !k.is_empty())
+            .collect::<Vec<_>>();
+
+        if input.categories.is_empty() && keywords.is_empty() && !input.similar_to_used {
+            return Err(async_graphql::Error::new(
+                "rule must have categories, keywords or similarToUsed",
+            ));
+        }
+
+        let rule = notification_rules::ActiveModel {
+            user_id: Set(user_id),
+            categories: Set(input.categories),
+            keywords: Set(keywords),
+            similar_to_used: Set(input.similar_to_used),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(NotificationRule(rule))
+    }
+
+    async fn remove_notification_rule(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+    ) -> async_graphql::Result<bool> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+
+        let res = notification_rules::Entity::delete_many()
+            .filter(notification_rules::Column::Id.eq(id))
+            .filter(notification_rules::Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+
+    async fn favourite_offer(
+        &self,
+        ctx: &Context<'_>,
+        input: FavouriteOfferInput,
+    ) -> async_graphql::Result<bool> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+
+        offer_favourites::Entity::insert(offer_favourites::ActiveModel {
+            user_id: Set(user_id),
+            short_name: Set(input.short_name),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([
+                offer_favourites::Column::UserId,
+                offer_favourites::Column::ShortName,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+
+        Ok(true)
+    }
+
+    async fn unfavourite_offer(
+        &self,
+        ctx: &Context<'_>,
+        input: FavouriteOfferInput,
+    ) -> async_graphql::Result<bool> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+
+        let res = offer_favourites::Entity::delete_by_id((user_id, input.short_name))
+            .exec(db)
+            .await?;
+
+        Ok(res.rows_affected > 0)
+    }
+}
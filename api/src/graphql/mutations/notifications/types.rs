@@ -0,0 +1,31 @@
+use async_graphql::InputObject;
+
+#[derive(InputObject)]
+pub struct SubscribePushInput {
+    // from PushSubscription.toJSON() in the browser
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+}
+
+#[derive(InputObject)]
+pub struct UnsubscribePushInput {
+    pub endpoint: String,
+}
+
+#[derive(InputObject)]
+pub struct AddNotificationRuleInput {
+    #[graphql(default)]
+    pub categories: Vec<String>,
+    // matched case insensitively against the offer name and description
+    #[graphql(default)]
+    pub keywords: Vec<String>,
+    // offers in the same cluster as ones the user redeems
+    #[graphql(default)]
+    pub similar_to_used: bool,
+}
+
+#[derive(InputObject)]
+pub struct FavouriteOfferInput {
+    pub short_name: String,
+}
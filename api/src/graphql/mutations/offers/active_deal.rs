@@ -0,0 +1,57 @@
+use anyhow::Context as _;
+use api::Event;
+use base::jwt::{JwtClaims, Role};
+use entity::{
+    events, offer_audit,
+    sea_orm_active_enums::{Action, EventStatus},
+};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    prelude::{Expr, Uuid},
+};
+
+pub struct ActiveDeal {
+    pub audit: offer_audit::Model,
+    pub cleanup: events::Model,
+    pub account_id: Uuid,
+}
+
+// the deal is active for as long as its Cleanup event is still pending
+pub async fn find_active_deal(
+    db: &DatabaseConnection,
+    transaction_id: Uuid,
+    claims: &JwtClaims,
+) -> async_graphql::Result<ActiveDeal> {
+    let user_id = claims.user_id.parse::<Uuid>()?;
+
+    let audit = offer_audit::Entity::find()
+        .filter(offer_audit::Column::TransactionId.eq(transaction_id))
+        .filter(offer_audit::Column::Action.eq(Action::Add))
+        .one(db)
+        .await?
+        .filter(|a| a.user_id == Some(user_id) || claims.role.contains(&Role::Admin))
+        .context("No deal found for this transaction")?;
+
+    let cleanup = events::Entity::find()
+        .filter(events::Column::Name.eq("Cleanup"))
+        .filter(events::Column::Status.eq(EventStatus::Pending))
+        .filter(Expr::cust_with_values(
+            "data -> 'Cleanup' ->> 'transaction_id' = $1",
+            [transaction_id.to_string()],
+        ))
+        .order_by_desc(events::Column::Id)
+        .one(db)
+        .await?
+        .context("Deal is no longer active")?;
+
+    let Event::Cleanup { account_id, .. } = serde_json::from_value::<Event>(cleanup.data.clone())?
+    else {
+        return Err("Deal is no longer active".into());
+    };
+
+    Ok(ActiveDeal {
+        audit,
+        cleanup,
+        account_id,
+    })
+}
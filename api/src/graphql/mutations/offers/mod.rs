@@ -1,176 +1,62 @@
-use self::types::{AddOfferInput, AddOfferResponse, RemoveOfferInput};
-use crate::{event_manager::EventManager, graphql::ValidatedClaims, settings::Settings};
+use self::{
+    active_deal::find_active_deal,
+    types::{
+        AddOfferInput, AddOfferResponse, ExtendOfferInput, ExtendOfferResponse, LeaveWaitlistInput,
+        ReleaseOfferInput, RemoveOfferInput,
+    },
+};
+use crate::{
+    deal_limits::UserDealLimits,
+    event_manager::EventManager,
+    graphql::ValidatedClaims,
+    offer_events::{
+        self, DealStackAction, DealStackChange, OfferEvent, OfferRemovedReason, publish_for_account,
+    },
+    reservations::{Reservation, ReservationError, join_waitlist, leave_waitlist, reserve_offer},
+    settings::Settings,
+};
 use anyhow::Context as _;
 use api::{CreateEvent, Event};
-use async_graphql::{Context, Object};
-use base::constants::mc_donalds::OFFSET;
+use async_graphql::{Context, ErrorExtensions, Object};
+use base::{constants::mc_donalds::OFFSET, jwt::Role};
 use entity::{accounts, concurrent_active_deals, offers, sea_orm_active_enums::Action};
 use opentelemetry::trace::TraceContextExt;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter,
-    QuerySelect, Set, TransactionTrait,
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+    TransactionTrait,
     prelude::{Expr, Uuid},
     sea_query::OnConflict,
 };
 use std::time::Duration;
 
-mod types;
+mod active_deal;
+pub mod types;
 
 #[derive(Default)]
 pub struct OffersMutation;
 
 #[Object]
 impl OffersMutation {
-    const CONCURRENT_OFFERS_LIMIT: i32 = 5;
+    const MAX_EXTENSION_MINUTES: i32 = 30;
+    // from when the offer was added
+    const MAX_DEAL_MINUTES: i64 = 60;
+
     async fn add_offer(
         &self,
         ctx: &Context<'_>,
         input: AddOfferInput,
     ) -> async_graphql::Result<AddOfferResponse> {
         let db = ctx.data::<DatabaseConnection>()?;
-
-        let claims = ctx.data_opt::<ValidatedClaims>();
-        let user_id = claims.and_then(|c| c.0.user_id.parse::<Uuid>().ok());
-        if let Some(user_id) = user_id {
-            let current_deal_count = concurrent_active_deals::Entity::find()
-                .filter(concurrent_active_deals::Column::UserId.eq(user_id))
-                .limit(1)
-                .one(db)
-                .await?;
-
-            tracing::info!("current active deals for {user_id}: {current_deal_count:?}");
-            if let Some(current_deal_count) = current_deal_count
-                && current_deal_count.count + 1 > Self::CONCURRENT_OFFERS_LIMIT {
-                    return Err(async_graphql::Error::new("cannot have more offers active"));
-                }
-        }
-
-        let all_locked_accounts = entity::account_lock::Entity::find()
-            .all(db)
-            .await?
-            .into_iter()
-            .map(|a| a.id);
-
-        let mut conditions = Condition::all();
-        for locked_account in all_locked_accounts {
-            conditions = conditions.add(offers::Column::AccountId.ne(locked_account));
-        }
-
-        let (offer, account) = offers::Entity::find()
-            .filter(conditions)
-            .filter(offers::Column::OfferPropositionId.eq(input.offer_proposition_id))
-            .find_also_related(accounts::Entity)
-            .one(db)
-            .await?
-            .context("No offer found for this id")?;
-
-        let account = account.context("Must find related account")?;
-
-        let in_15_minutes = chrono::offset::Utc::now().naive_utc() + Duration::from_secs(900);
-        entity::account_lock::ActiveModel {
-            id: Set(offer.account_id),
-            unlock_at: Set(in_15_minutes),
-            ..Default::default()
-        }
-        .insert(db)
-        .await?;
-
-        let offer_id = offer.id;
-        let validated_proposition_id = input.offer_proposition_id;
         let settings = ctx.data::<Settings>()?;
-
-        let proxy = reqwest::Proxy::all(settings.proxy.url.clone())?
-            .basic_auth(&settings.proxy.username, &settings.proxy.password);
-
-        let account_lock_txn = db.begin().await?;
-        let api_client = base::maccas::get_activated_maccas_api_client(
-            account,
-            proxy,
-            &settings.mcdonalds.client_id,
-            &account_lock_txn,
-        )
-        .await?;
-        account_lock_txn.commit().await?;
-
-        let deal_stack_response = api_client
-            .add_to_offers_dealstack(&offer.offer_proposition_id, OFFSET, &input.store_id)
-            .await?;
-
-        let deal_stack_response = deal_stack_response
-            .body
-            .response
-            .context("Must have added offer")?;
-
-        let transaction_id = Uuid::new_v4();
-        let audit_id = entity::offer_audit::ActiveModel {
-            action: Set(Action::Add),
-            proposition_id: Set(validated_proposition_id),
-            user_id: Set(claims.and_then(|c| c.0.user_id.parse().ok())),
-            transaction_id: Set(transaction_id),
-            store_id: Set(Some(input.store_id.clone())),
-            ..Default::default()
-        }
-        .insert(db)
-        .await?
-        .id;
-
-        if let Some(user_id) = user_id {
-            let active_deals_model = concurrent_active_deals::ActiveModel {
-                user_id: Set(user_id),
-                count: Set(1),
-            };
-
-            concurrent_active_deals::Entity::insert(active_deals_model)
-                .on_conflict(
-                    OnConflict::column(concurrent_active_deals::Column::UserId)
-                        .value(
-                            concurrent_active_deals::Column::Count,
-                            Expr::cust_with_expr(
-                                "LEAST($1, 5)",
-                                Expr::column((
-                                    concurrent_active_deals::Entity,
-                                    concurrent_active_deals::Column::Count,
-                                ))
-                                .add(Expr::value(1)),
-                            ),
-                        )
-                        .to_owned(),
-                )
-                .exec(db)
-                .await?;
-        }
-
-        let cleanup_event = CreateEvent {
-            event: Event::Cleanup {
-                offer_id,
-                transaction_id,
-                store_id: input.store_id,
-                audit_id,
-                user_id: claims.and_then(|c| c.0.user_id.parse().ok()),
-                // account.id
-                account_id: offer.account_id,
-            },
-            delay: Duration::from_secs(900),
-        };
-
         let event_manager = ctx.data::<EventManager>()?;
-        let trace_id = opentelemetry::Context::current()
-            .span()
-            .span_context()
-            .trace_id()
-            .to_string();
-
-        if let Err(e) = event_manager
-            .create_event(cleanup_event.event, cleanup_event.delay, trace_id)
-            .await
-        {
-            tracing::error!("error creating event: {e}")
-        }
 
-        Ok(AddOfferResponse {
-            id: offer_id,
-            code: deal_stack_response.random_code,
-        })
+        let claims = ctx.data_opt::<ValidatedClaims>();
+        let user = claims.and_then(|c| {
+            let user_id = c.0.user_id.parse::<Uuid>().ok()?;
+            Some((user_id, c.0.role.as_slice()))
+        });
+
+        add_to_deal_stack(db, settings, event_manager, user, input).await
     }
 
     async fn remove_offer(
@@ -193,15 +79,13 @@ impl OffersMutation {
         let proxy = reqwest::Proxy::all(settings.proxy.url.clone())?
             .basic_auth(&settings.proxy.username, &settings.proxy.password);
 
-        let account_lock_txn = db.begin().await?;
         let api_client = base::maccas::get_activated_maccas_api_client(
             account,
             proxy,
             &settings.mcdonalds.client_id,
-            &account_lock_txn,
+            db,
         )
         .await?;
-        account_lock_txn.commit().await?;
 
         let response = api_client
             .remove_from_offers_dealstack(
@@ -213,25 +97,51 @@ impl OffersMutation {
             .await?;
 
         let claims = ctx.data_opt::<ValidatedClaims>();
+        let user_id = claims.and_then(|c| c.0.user_id.parse::<Uuid>().ok());
 
         if response.status.is_success() {
             entity::account_lock::Entity::delete_by_id(offer.account_id)
                 .exec(db)
                 .await?;
 
+            // subscribers to dealStackChanged only know the transaction from addOffer
+            let mut added_audit = entity::offer_audit::Entity::find()
+                .filter(entity::offer_audit::Column::Action.eq(Action::Add))
+                .filter(entity::offer_audit::Column::PropositionId.eq(offer.offer_proposition_id))
+                .order_by_desc(entity::offer_audit::Column::Id);
+            if let Some(user_id) = user_id {
+                added_audit = added_audit.filter(entity::offer_audit::Column::UserId.eq(user_id));
+            }
+            let transaction_id = added_audit
+                .one(db)
+                .await?
+                .map(|a| a.transaction_id)
+                .unwrap_or_else(Uuid::new_v4);
+
             entity::offer_audit::ActiveModel {
                 action: Set(Action::Remove),
                 proposition_id: Set(offer.offer_proposition_id),
-                // FIXME: find old one ?
-                user_id: Set(claims.and_then(|c| c.0.user_id.parse().ok())),
-                transaction_id: Set(Uuid::new_v4()),
+                user_id: Set(user_id),
+                transaction_id: Set(transaction_id),
                 ..Default::default()
             }
             .insert(db)
             .await?;
+
+            let removed_event = OfferEvent::DealStackChanged(DealStackChange {
+                transaction_id,
+                offer_proposition_id: offer.offer_proposition_id,
+                action: DealStackAction::Removed,
+            });
+            if let Err(e) = offer_events::publish(db, &removed_event).await {
+                tracing::warn!("error publishing deal stack change: {e}");
+            }
+
+            if let Err(e) = publish_for_account(db, offer.account_id, OfferEvent::added).await {
+                tracing::warn!("error publishing unlocked offers: {e}");
+            }
         }
 
-        let user_id = claims.and_then(|c| c.0.user_id.parse::<Uuid>().ok());
         if let Some(user_id) = user_id {
             let active_deals_model = concurrent_active_deals::ActiveModel {
                 user_id: Set(user_id),
@@ -260,4 +170,301 @@ impl OffersMutation {
 
         Ok(input.id)
     }
+
+    async fn leave_waitlist(
+        &self,
+        ctx: &Context<'_>,
+        input: LeaveWaitlistInput,
+    ) -> async_graphql::Result<bool> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+
+        Ok(leave_waitlist(db, user_id, input.offer_proposition_id).await?)
+    }
+
+    async fn extend_offer(
+        &self,
+        ctx: &Context<'_>,
+        input: ExtendOfferInput,
+    ) -> async_graphql::Result<ExtendOfferResponse> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let claims = ctx.data::<ValidatedClaims>()?;
+
+        if !(1..=Self::MAX_EXTENSION_MINUTES).contains(&input.minutes) {
+            return Err(format!(
+                "minutes must be between 1 and {}",
+                Self::MAX_EXTENSION_MINUTES
+            )
+            .into());
+        }
+
+        let deal = find_active_deal(db, input.transaction_id, &claims.0).await?;
+        let lock = entity::account_lock::Entity::find_by_id(deal.account_id)
+            .one(db)
+            .await?
+            .context("Deal is no longer active")?;
+
+        let now = chrono::offset::Utc::now().naive_utc();
+        let latest_unlock_at =
+            deal.audit.created_at + chrono::Duration::minutes(Self::MAX_DEAL_MINUTES);
+        let unlock_at = (lock.unlock_at.max(now) + chrono::Duration::minutes(input.minutes.into()))
+            .min(latest_unlock_at);
+
+        if unlock_at <= lock.unlock_at {
+            return Err("Deal cannot be extended any further".into());
+        }
+
+        entity::account_lock::Entity::update(entity::account_lock::ActiveModel {
+            id: sea_orm::Unchanged(lock.id),
+            unlock_at: Set(unlock_at),
+            ..Default::default()
+        })
+        .exec(db)
+        .await?;
+
+        let event_manager = ctx.data::<EventManager>()?;
+        let rescheduled = event_manager
+            .reschedule_event(deal.cleanup.id, (unlock_at - now).to_std()?)
+            .await?;
+        if !rescheduled {
+            return Err("Deal is no longer active".into());
+        }
+
+        entity::offer_audit::ActiveModel {
+            action: Set(Action::Extend),
+            proposition_id: Set(deal.audit.proposition_id),
+            user_id: Set(deal.audit.user_id),
+            transaction_id: Set(input.transaction_id),
+            store_id: Set(deal.audit.store_id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(ExtendOfferResponse {
+            transaction_id: input.transaction_id,
+            unlock_at: unlock_at.and_utc(),
+        })
+    }
+
+    // runs the pending Cleanup now, which takes the offer out of the deal stack and unlocks
+    // the account
+    async fn release_offer(
+        &self,
+        ctx: &Context<'_>,
+        input: ReleaseOfferInput,
+    ) -> async_graphql::Result<Uuid> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let claims = ctx.data::<ValidatedClaims>()?;
+
+        let deal = find_active_deal(db, input.transaction_id, &claims.0).await?;
+
+        // ends the deal for the limits straight away. it goes in before Cleanup is brought
+        // forward, so Cleanup always sees it and doesn't add a second one
+        let removed = entity::offer_audit::ActiveModel {
+            action: Set(Action::Remove),
+            proposition_id: Set(deal.audit.proposition_id),
+            user_id: Set(deal.audit.user_id),
+            transaction_id: Set(input.transaction_id),
+            store_id: Set(deal.audit.store_id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        let event_manager = ctx.data::<EventManager>()?;
+        match event_manager
+            .reschedule_event(deal.cleanup.id, Duration::ZERO)
+            .await
+        {
+            Ok(true) => Ok(input.transaction_id),
+            // Cleanup is still pending and will write its own
+            Err(e) => {
+                entity::offer_audit::Entity::delete_by_id(removed.id)
+                    .exec(db)
+                    .await?;
+
+                Err(e.into())
+            }
+            // Cleanup already started, ours stays unless it got in first with its own
+            Ok(false) => {
+                let earlier_remove = entity::offer_audit::Entity::find()
+                    .filter(entity::offer_audit::Column::TransactionId.eq(input.transaction_id))
+                    .filter(entity::offer_audit::Column::Action.eq(Action::Remove))
+                    .filter(entity::offer_audit::Column::Id.ne(removed.id))
+                    .one(db)
+                    .await?;
+
+                if earlier_remove.is_some() {
+                    entity::offer_audit::Entity::delete_by_id(removed.id)
+                        .exec(db)
+                        .await?;
+                }
+
+                Err("Deal is no longer active".into())
+            }
+        }
+    }
+}
+
+// addOffer and the discord /deal command, user is None for callers without a token
+pub async fn add_to_deal_stack(
+    db: &DatabaseConnection,
+    settings: &Settings,
+    event_manager: &EventManager,
+    user: Option<(Uuid, &[Role])>,
+    input: AddOfferInput,
+) -> async_graphql::Result<AddOfferResponse> {
+    let user_id = user.map(|(user_id, _)| user_id);
+    // the Add audit is inserted through this so the limit lock covers it
+    let limits_txn = db.begin().await?;
+    let mut concurrent_limit = None;
+    if let Some((user_id, roles)) = user {
+        let limits = UserDealLimits::for_user(db, &settings.deal_limits, roles, user_id).await?;
+        tracing::info!("deal limits for {user_id}: {limits:?}");
+
+        UserDealLimits::lock(&limits_txn, user_id).await?;
+        limits
+            .check(&limits_txn, user_id, input.offer_proposition_id)
+            .await
+            .map_err(|e| e.extend())?;
+        concurrent_limit = limits.concurrent;
+    }
+
+    let reservation = reserve_offer(db, input.offer_proposition_id, Duration::from_secs(900)).await;
+    if let Err(ReservationError::NoAccountsAvailable {
+        offer_proposition_id,
+        ..
+    }) = reservation
+        && input.join_waitlist
+        && let Some(user_id) = user_id
+    {
+        join_waitlist(db, user_id, offer_proposition_id).await?;
+        return Err(ReservationError::NoAccountsAvailable {
+            offer_proposition_id,
+            waitlisted: true,
+        }
+        .extend());
+    }
+    let Reservation { offer, account } = reservation.map_err(|e| e.extend())?;
+
+    if let Err(e) = publish_for_account(db, offer.account_id, |o| {
+        OfferEvent::removed(o, OfferRemovedReason::Locked)
+    })
+    .await
+    {
+        tracing::warn!("error publishing locked offers: {e}");
+    }
+
+    let offer_id = offer.id;
+    let validated_proposition_id = input.offer_proposition_id;
+
+    let proxy = reqwest::Proxy::all(settings.proxy.url.clone())?
+        .basic_auth(&settings.proxy.username, &settings.proxy.password);
+
+    let api_client = base::maccas::get_activated_maccas_api_client(
+        account,
+        proxy,
+        &settings.mcdonalds.client_id,
+        db,
+    )
+    .await?;
+
+    let deal_stack_response = api_client
+        .add_to_offers_dealstack(&offer.offer_proposition_id, OFFSET, &input.store_id)
+        .await?;
+
+    let deal_stack_response = deal_stack_response
+        .body
+        .response
+        .context("Must have added offer")?;
+
+    let transaction_id = Uuid::new_v4();
+    let audit_id = entity::offer_audit::ActiveModel {
+        action: Set(Action::Add),
+        proposition_id: Set(validated_proposition_id),
+        user_id: Set(user_id),
+        transaction_id: Set(transaction_id),
+        store_id: Set(Some(input.store_id.clone())),
+        ..Default::default()
+    }
+    .insert(&limits_txn)
+    .await?
+    .id;
+
+    // active_transactions only counts the deal once its Cleanup exists, so it's created
+    // while the limit lock is still held. without it the deal would never count at all
+    let cleanup_event = CreateEvent {
+        event: Event::Cleanup {
+            offer_id,
+            transaction_id,
+            store_id: input.store_id,
+            audit_id,
+            user_id,
+            // account.id
+            account_id: offer.account_id,
+        },
+        delay: Duration::from_secs(900),
+    };
+
+    let trace_id = opentelemetry::Context::current()
+        .span()
+        .span_context()
+        .trace_id()
+        .to_string();
+
+    event_manager
+        .create_event(cleanup_event.event, cleanup_event.delay, trace_id)
+        .await?;
+
+    limits_txn.commit().await?;
+
+    if let Some(user_id) = user_id
+        && let Err(e) = leave_waitlist(db, user_id, validated_proposition_id).await
+    {
+        tracing::warn!("error removing {user_id} from waitlist: {e}");
+    }
+
+    let added_event = OfferEvent::DealStackChanged(DealStackChange {
+        transaction_id,
+        offer_proposition_id: validated_proposition_id,
+        action: DealStackAction::Added,
+    });
+    if let Err(e) = offer_events::publish(db, &added_event).await {
+        tracing::warn!("error publishing deal stack change: {e}");
+    }
+
+    if let Some(user_id) = user_id {
+        let active_deals_model = concurrent_active_deals::ActiveModel {
+            user_id: Set(user_id),
+            count: Set(1),
+        };
+
+        let incremented = Expr::column((
+            concurrent_active_deals::Entity,
+            concurrent_active_deals::Column::Count,
+        ))
+        .add(Expr::value(1));
+        let count = match concurrent_limit {
+            Some(limit) => {
+                Expr::cust_with_exprs("LEAST($1, $2)", [incremented, Expr::value(limit)])
+            }
+            None => incremented,
+        };
+
+        concurrent_active_deals::Entity::insert(active_deals_model)
+            .on_conflict(
+                OnConflict::column(concurrent_active_deals::Column::UserId)
+                    .value(concurrent_active_deals::Column::Count, count)
+                    .to_owned(),
+            )
+            .exec(db)
+            .await?;
+    }
+
+    Ok(AddOfferResponse {
+        id: offer_id,
+        code: deal_stack_response.random_code,
+        transaction_id,
+    })
 }
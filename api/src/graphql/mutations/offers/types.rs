@@ -1,10 +1,14 @@
 use async_graphql::{InputObject, SimpleObject};
+use chrono::{DateTime, Utc};
 use sea_orm::prelude::Uuid;
 
 #[derive(InputObject)]
 pub struct AddOfferInput {
     pub offer_proposition_id: i64,
     pub store_id: String,
+    // queue for the offer when every account holding it is locked
+    #[graphql(default)]
+    pub join_waitlist: bool,
 }
 
 #[derive(InputObject)]
@@ -13,8 +17,32 @@ pub struct RemoveOfferInput {
     pub store_id: String,
 }
 
+#[derive(InputObject)]
+pub struct LeaveWaitlistInput {
+    pub offer_proposition_id: i64,
+}
+
 #[derive(SimpleObject)]
 pub struct AddOfferResponse {
     pub id: Uuid,
     pub code: String,
+    // for dealStackChanged
+    pub transaction_id: Uuid,
+}
+
+#[derive(InputObject)]
+pub struct ExtendOfferInput {
+    pub transaction_id: Uuid,
+    pub minutes: i32,
+}
+
+#[derive(InputObject)]
+pub struct ReleaseOfferInput {
+    pub transaction_id: Uuid,
+}
+
+#[derive(SimpleObject)]
+pub struct ExtendOfferResponse {
+    pub transaction_id: Uuid,
+    pub unlock_at: DateTime<Utc>,
 }
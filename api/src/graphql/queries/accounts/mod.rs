@@ -0,0 +1,183 @@
+use self::types::{AccountCounts, AccountOffers, AccountPool, AgeBucket, AgeCount, PointsBucket};
+use crate::graphql::guard::RoleGuard;
+use anyhow::Context as _;
+use async_graphql::{Context, Object};
+use base::{constants::MACCAS_ACCOUNT_REFRESH_FAILURE, jwt::Role};
+use entity::{accounts, points};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbBackend, EntityTrait, FromQueryResult, Order, QueryFilter,
+    QueryOrder, QuerySelect, Statement, sea_query::Expr,
+};
+
+pub mod types;
+
+const DEFAULT_POINTS_BUCKET_SIZE: i64 = 1000;
+// lower bounds, anything past the last one shares a bucket
+const AGE_BUCKET_HOURS: [i32; 7] = [0, 1, 6, 12, 24, 48, 168];
+
+const COUNTS_SQL: &str = r#"
+SELECT
+    count(*) AS total,
+    count(*) FILTER (WHERE a.active AND a.refresh_failure_count <= $1) AS active,
+    count(*) FILTER (WHERE NOT a.active) AS disabled,
+    count(*) FILTER (WHERE a.refresh_failure_count > $1) AS failing,
+    count(l.id) AS locked,
+    count(*) FILTER (WHERE a.offers_refreshed_at <= a.created_at) AS never_activated
+FROM accounts a
+LEFT JOIN account_lock l ON l.id = a.id
+"#;
+
+const OFFERS_PER_ACCOUNT_SQL: &str = r#"
+SELECT
+    a.id AS account_id,
+    a.username,
+    a.active,
+    a.refresh_failure_count,
+    l.unlock_at AS locked_until,
+    p.current_points,
+    count(o.id) AS offers,
+    a.refreshed_at,
+    a.offers_refreshed_at
+FROM accounts a
+LEFT JOIN account_lock l ON l.id = a.id
+LEFT JOIN points p ON p.account_id = a.id
+LEFT JOIN offers o ON o.account_id = a.id
+GROUP BY a.id, l.unlock_at, p.current_points
+ORDER BY offers ASC, a.username ASC
+"#;
+
+#[derive(Default)]
+pub struct AccountsQuery;
+
+#[Object]
+impl AccountsQuery {
+    #[graphql(guard = "RoleGuard::with_role(Role::Admin)")]
+    async fn account_pool(&self) -> async_graphql::Result<AccountPool> {
+        Ok(AccountPool {})
+    }
+}
+
+#[Object]
+impl AccountPool {
+    async fn counts<'a>(&self, ctx: &Context<'a>) -> async_graphql::Result<AccountCounts> {
+        let db = ctx.data::<DatabaseConnection>()?;
+
+        Ok(
+            AccountCounts::find_by_statement(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                COUNTS_SQL,
+                [MACCAS_ACCOUNT_REFRESH_FAILURE.into()],
+            ))
+            .one(db)
+            .await?
+            .context("aggregate must return a row")?,
+        )
+    }
+
+    // fewest offers first, those are the accounts worth looking at
+    async fn offers_per_account<'a>(
+        &self,
+        ctx: &Context<'a>,
+    ) -> async_graphql::Result<Vec<AccountOffers>> {
+        let db = ctx.data::<DatabaseConnection>()?;
+
+        Ok(AccountOffers::find_by_statement(Statement::from_string(
+            DbBackend::Postgres,
+            OFFERS_PER_ACCOUNT_SQL,
+        ))
+        .all(db)
+        .await?)
+    }
+
+    // enabled accounts only, a disabled account's points can't be spent anyway
+    async fn points_distribution<'a>(
+        &self,
+        ctx: &Context<'a>,
+        bucket_size: Option<i64>,
+    ) -> async_graphql::Result<Vec<PointsBucket>> {
+        let db = ctx.data::<DatabaseConnection>()?;
+
+        let bucket_size = bucket_size.unwrap_or(DEFAULT_POINTS_BUCKET_SIZE);
+        if bucket_size <= 0 {
+            return Err(anyhow::Error::msg("bucket size must be positive").into());
+        }
+
+        Ok(points::Entity::find()
+            .select_only()
+            .column_as(
+                Expr::cust_with_values("(points.current_points / $1) * $1", [bucket_size]),
+                "min_points",
+            )
+            .column_as(Expr::cust("count(*)"), "accounts")
+            .inner_join(accounts::Entity)
+            .filter(accounts::Column::Active.eq(true))
+            .group_by(Expr::cust("min_points"))
+            .order_by(Expr::cust("min_points"), Order::Asc)
+            .into_model::<PointsBucket>()
+            .all(db)
+            .await?)
+    }
+
+    // time since the access token was last refreshed
+    async fn token_age<'a>(&self, ctx: &Context<'a>) -> async_graphql::Result<Vec<AgeBucket>> {
+        let db = ctx.data::<DatabaseConnection>()?;
+
+        Ok(age_histogram(db, "refreshed_at").await?)
+    }
+
+    // time since the deal stack was last fetched
+    async fn offers_staleness<'a>(
+        &self,
+        ctx: &Context<'a>,
+    ) -> async_graphql::Result<Vec<AgeBucket>> {
+        let db = ctx.data::<DatabaseConnection>()?;
+
+        Ok(age_histogram(db, "offers_refreshed_at").await?)
+    }
+}
+
+// column is a timestamp on accounts, empty buckets are included so the shape is stable
+async fn age_histogram(
+    db: &DatabaseConnection,
+    column: &'static str,
+) -> Result<Vec<AgeBucket>, sea_orm::DbErr> {
+    let now = chrono::offset::Utc::now().naive_utc();
+    let thresholds = AGE_BUCKET_HOURS
+        .iter()
+        .map(i32::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    // width_bucket numbers from 1 when the value is at least the first threshold
+    let counts = accounts::Entity::find()
+        .select_only()
+        .column_as(
+            Expr::cust_with_values(
+                format!(
+                    "width_bucket(greatest(extract(epoch FROM $1 - accounts.{column})::double precision, 0) / 3600, ARRAY[{thresholds}]::double precision[])"
+                ),
+                [now],
+            ),
+            "bucket",
+        )
+        .column_as(Expr::cust("count(*)"), "accounts")
+        .filter(accounts::Column::Active.eq(true))
+        .group_by(Expr::cust("bucket"))
+        .into_model::<AgeCount>()
+        .all(db)
+        .await?;
+
+    Ok(AGE_BUCKET_HOURS
+        .iter()
+        .enumerate()
+        .map(|(i, min_hours)| AgeBucket {
+            min_hours: *min_hours,
+            max_hours: AGE_BUCKET_HOURS.get(i + 1).copied(),
+            accounts: counts
+                .iter()
+                .find(|c| usize::try_from(c.bucket).is_ok_and(|b| b == i + 1))
+                .map(|c| c.accounts)
+                .unwrap_or_default(),
+        })
+        .collect())
+}
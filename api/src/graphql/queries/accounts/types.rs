@@ -0,0 +1,54 @@
+use async_graphql::SimpleObject;
+use sea_orm::{
+    FromQueryResult,
+    prelude::{DateTime, Uuid},
+};
+
+pub struct AccountPool {}
+
+#[derive(SimpleObject, FromQueryResult)]
+pub struct AccountCounts {
+    pub total: i64,
+    // enabled and under the refresh failure limit, what the jobs actually pick from
+    pub active: i64,
+    pub disabled: i64,
+    pub failing: i64,
+    pub locked: i64,
+    // created but never got through an offer refresh, usually still waiting on the
+    // activation email
+    pub never_activated: i64,
+}
+
+#[derive(SimpleObject, FromQueryResult)]
+pub struct AccountOffers {
+    pub account_id: Uuid,
+    pub username: String,
+    pub active: bool,
+    pub refresh_failure_count: i32,
+    pub locked_until: Option<DateTime>,
+    pub current_points: Option<i64>,
+    pub offers: i64,
+    pub refreshed_at: DateTime,
+    pub offers_refreshed_at: DateTime,
+}
+
+#[derive(SimpleObject, FromQueryResult)]
+pub struct PointsBucket {
+    // inclusive, the bucket runs up to min_points + bucket size
+    pub min_points: i64,
+    pub accounts: i64,
+}
+
+#[derive(SimpleObject)]
+pub struct AgeBucket {
+    pub min_hours: i32,
+    // empty for the last bucket
+    pub max_hours: Option<i32>,
+    pub accounts: i64,
+}
+
+#[derive(FromQueryResult)]
+pub struct AgeCount {
+    pub bucket: i32,
+    pub accounts: i64,
+}
@@ -0,0 +1,96 @@
+use self::types::{HistoryConnection, HistoryCursor, HistoryEntry};
+use crate::graphql::ValidatedClaims;
+use async_graphql::{
+    Context, Object,
+    connection::{Connection, Edge, OpaqueCursor, query},
+};
+use entity::{offer_audit, offer_details, sea_orm_active_enums::Action, stores};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    prelude::Uuid,
+};
+use std::collections::{HashMap, HashSet};
+
+mod types;
+
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 100;
+
+#[derive(Default)]
+pub struct HistoryQuery;
+
+#[Object]
+impl HistoryQuery {
+    async fn my_history(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> async_graphql::Result<HistoryConnection> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+
+        query(
+            after,
+            None,
+            first,
+            None,
+            |after: Option<HistoryCursor>, _, first, _| async move {
+                let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+                // every Add is a redemption, ids only go up so they double as the cursor
+                let mut conditions = Condition::all()
+                    .add(offer_audit::Column::UserId.eq(user_id))
+                    .add(offer_audit::Column::Action.eq(Action::Add));
+                if let Some(after) = &after {
+                    conditions = conditions.add(offer_audit::Column::Id.lt(after.0));
+                }
+
+                let mut rows = offer_audit::Entity::find()
+                    .find_also_related(offer_details::Entity)
+                    .filter(conditions)
+                    .order_by_desc(offer_audit::Column::Id)
+                    .limit(limit as u64 + 1)
+                    .all(db)
+                    .await?;
+
+                let has_next_page = rows.len() > limit;
+                rows.truncate(limit);
+
+                let store_ids = rows
+                    .iter()
+                    .filter_map(|(audit, _)| audit.store_id.clone())
+                    .collect::<HashSet<_>>();
+                let store_names = stores::Entity::find()
+                    .filter(stores::Column::Id.is_in(store_ids))
+                    .all(db)
+                    .await?
+                    .into_iter()
+                    .map(|s| (s.id, s.name))
+                    .collect::<HashMap<_, _>>();
+
+                let mut connection = Connection::new(after.is_some(), has_next_page);
+                connection
+                    .edges
+                    .extend(rows.into_iter().map(|(audit, details)| {
+                        let store_name = audit
+                            .store_id
+                            .as_ref()
+                            .and_then(|id| store_names.get(id).cloned());
+
+                        Edge::new(
+                            OpaqueCursor(audit.id),
+                            HistoryEntry {
+                                audit,
+                                details,
+                                store_name,
+                            },
+                        )
+                    }));
+
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
+}
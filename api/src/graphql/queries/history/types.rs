@@ -0,0 +1,61 @@
+use async_graphql::{
+    Object,
+    connection::{Connection, OpaqueCursor},
+};
+use base::constants::{IMAGE_BASE_URL, IMAGE_EXT};
+use entity::{offer_audit, offer_details};
+use sea_orm::prelude::{DateTime, Uuid};
+
+pub type HistoryCursor = OpaqueCursor<i32>;
+pub type HistoryConnection = Connection<HistoryCursor, HistoryEntry>;
+
+pub struct HistoryEntry {
+    pub audit: offer_audit::Model,
+    pub details: Option<offer_details::Model>,
+    pub store_name: Option<String>,
+}
+
+#[Object]
+impl HistoryEntry {
+    pub async fn transaction_id(&self) -> &Uuid {
+        &self.audit.transaction_id
+    }
+
+    pub async fn offer_proposition_id(&self) -> &i64 {
+        &self.audit.proposition_id
+    }
+
+    pub async fn name(&self) -> Option<&String> {
+        self.details.as_ref().map(|d| &d.name)
+    }
+
+    pub async fn short_name(&self) -> Option<&String> {
+        self.details.as_ref().map(|d| &d.short_name)
+    }
+
+    pub async fn image_url(&self) -> Option<String> {
+        self.details
+            .as_ref()
+            .map(|d| format!("{IMAGE_BASE_URL}/{}.{IMAGE_EXT}", d.image_base_name))
+    }
+
+    pub async fn price(&self) -> Option<f64> {
+        self.details.as_ref().and_then(|d| d.price)
+    }
+
+    pub async fn store_id(&self) -> &Option<String> {
+        &self.audit.store_id
+    }
+
+    pub async fn store_name(&self) -> &Option<String> {
+        &self.store_name
+    }
+
+    pub async fn likely_used(&self) -> &Option<bool> {
+        &self.audit.likely_used
+    }
+
+    pub async fn redeemed_at(&self) -> &DateTime {
+        &self.audit.created_at
+    }
+}
@@ -1,19 +1,91 @@
 use super::types::{Location, LocationRequest};
-use crate::settings::Settings;
+use crate::{
+    jobs::{shared, sync_stores::SEARCH_AREA_LIFETIME},
+    settings::Settings,
+};
 use async_graphql::dataloader::Loader;
 use base::{
     constants::mc_donalds::{FILTER, LOCATION_SEARCH_DISTANCE, STORE_UNIQUE_ID_TYPE},
     maccas,
 };
-use entity::accounts;
-use sea_orm::{DatabaseConnection, EntityTrait, QueryOrder, TransactionTrait};
-use std::{collections::HashMap, sync::Arc};
+use converters::Database;
+use entity::{accounts, store_search_areas, stores};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, Order, QueryFilter, QueryOrder,
+    QuerySelect,
+    sea_query::{Expr, SimpleExpr},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 pub struct LocationLoader {
     pub database: DatabaseConnection,
     pub settings: Settings,
 }
 
+// how far around the search point synced stores count as a hit
+const NEARBY_STORE_RADIUS_METERS: f64 = 10_000.0;
+const NEARBY_STORE_LIMIT: u64 = 20;
+
+fn to_location(loc: &LocationRequest, model: stores::Model) -> Location {
+    let user_location = geoutils::Location::new(loc.lat, loc.long);
+    let distance = model
+        .latitude
+        .zip(model.longitude)
+        .and_then(|(latitude, longitude)| {
+            user_location
+                .distance_to(&geoutils::Location::new(latitude, longitude))
+                .ok()
+        })
+        .map(|d| d.meters());
+
+    Location { model, distance }
+}
+
+// haversine, in meters, from the table's latitude and longitude columns
+fn distance_from(loc: &LocationRequest) -> SimpleExpr {
+    Expr::cust_with_values(
+        "6371000 * 2 * asin(sqrt(power(sin(radians(latitude - $1) / 2), 2) + cos(radians($1)) * cos(radians(latitude)) * power(sin(radians(longitude - $2) / 2), 2)))",
+        [loc.lat, loc.long],
+    )
+}
+
+pub async fn find_nearby_stores(
+    db: &DatabaseConnection,
+    loc: &LocationRequest,
+) -> Result<Vec<Location>, DbErr> {
+    let distance = distance_from(loc);
+
+    Ok(stores::Entity::find()
+        .filter(stores::Column::Latitude.is_not_null())
+        .filter(stores::Column::Longitude.is_not_null())
+        .filter(Expr::expr(distance.clone()).lte(NEARBY_STORE_RADIUS_METERS))
+        .order_by(distance, Order::Asc)
+        .limit(NEARBY_STORE_LIMIT)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|model| to_location(loc, model))
+        .collect())
+}
+
+// whether a recent sync searched a circle holding the whole nearby radius, only then is
+// finding few or no stores here an answer rather than a gap in the sync
+async fn is_synced_area(db: &DatabaseConnection, loc: &LocationRequest) -> Result<bool, DbErr> {
+    let synced_after = chrono::offset::Utc::now().naive_utc() - SEARCH_AREA_LIFETIME;
+
+    Ok(store_search_areas::Entity::find()
+        .filter(store_search_areas::Column::SearchedAt.gt(synced_after))
+        .filter(Expr::expr(distance_from(loc)).lte(
+            Expr::col(store_search_areas::Column::RadiusMeters).sub(NEARBY_STORE_RADIUS_METERS),
+        ))
+        .one(db)
+        .await?
+        .is_some())
+}
+
 impl Loader<LocationRequest> for LocationLoader {
     type Value = Vec<Location>;
     type Error = Arc<anyhow::Error>;
@@ -22,6 +94,29 @@ impl Loader<LocationRequest> for LocationLoader {
         &self,
         keys: &[LocationRequest],
     ) -> Result<HashMap<LocationRequest, Self::Value>, Self::Error> {
+        let mut location_map = HashMap::new();
+        let mut misses = Vec::new();
+        for loc in keys {
+            let nearby = find_nearby_stores(&self.database, loc)
+                .await
+                .map_err(|e| Arc::new(e.into()))?;
+
+            let is_full = nearby.len() as u64 >= NEARBY_STORE_LIMIT;
+            if is_full
+                || is_synced_area(&self.database, loc)
+                    .await
+                    .map_err(|e| Arc::new(e.into()))?
+            {
+                location_map.insert(loc.clone(), nearby);
+            } else {
+                misses.push((loc, nearby));
+            }
+        }
+
+        if misses.is_empty() {
+            return Ok(location_map);
+        }
+
         // pick more recently updated account
         let account_to_use = accounts::Entity::find()
             .order_by_desc(accounts::Column::UpdatedAt)
@@ -37,79 +132,68 @@ impl Loader<LocationRequest> for LocationLoader {
             .map_err(|e| Arc::new(e.into()))?
             .basic_auth(&self.settings.proxy.username, &self.settings.proxy.password);
 
-        let account_lock_txn = self
-            .database
-            .begin()
-            .await
-            .map_err(|e| Arc::new(e.into()))?;
-
         let api_client = maccas::get_activated_maccas_api_client(
             account_to_use,
             proxy,
             &self.settings.mcdonalds.client_id,
-            &account_lock_txn,
+            &self.database,
         )
         .await?;
 
-        account_lock_txn
-            .commit()
-            .await
-            .map_err(|e| Arc::new(e.into()))?;
-
         // FIXME: parallel
-        let mut location_map = HashMap::new();
-        for loc in keys {
+        for (loc, nearby) in misses {
             let response = api_client
                 .restaurant_location(&LOCATION_SEARCH_DISTANCE, &loc.lat, &loc.long, FILTER)
                 .await;
 
-            match response {
-                Ok(v) => {
-                    location_map.insert(loc, v);
+            // whatever was already synced is still better than nothing
+            let restaurants = match response {
+                Ok(v) => v.body.response.map(|r| r.restaurants).unwrap_or_default(),
+                Err(e) => {
+                    tracing::error!("error fetching location: {}", e);
+                    location_map.insert(loc.clone(), nearby);
+                    continue;
                 }
-                Err(e) => tracing::error!("error fetching location: {}", e),
             };
+
+            let models = restaurants
+                .iter()
+                .flat_map(Database::<stores::Model>::convert_restaurant_summary)
+                .map(|m| m.0)
+                .collect::<Vec<_>>();
+
+            // remember these so the next search around here doesn't go upstream
+            if let Err(e) = shared::upsert_store_summaries(&self.database, models.clone()).await {
+                tracing::warn!("error saving stores: {}", e);
+            }
+
+            // upstream only searches close by, the synced stores fill out the rest
+            let upstream_ids = models.iter().map(|m| m.id.clone()).collect::<HashSet<_>>();
+            let mut locations = models
+                .into_iter()
+                .map(|m| to_location(loc, m))
+                .chain(
+                    nearby
+                        .into_iter()
+                        .filter(|l| !upstream_ids.contains(&l.model.id)),
+                )
+                .collect::<Vec<_>>();
+            locations.sort_by(|a, b| {
+                a.distance
+                    .unwrap_or(f64::MAX)
+                    .total_cmp(&b.distance.unwrap_or(f64::MAX))
+            });
+            locations.truncate(NEARBY_STORE_LIMIT as usize);
+
+            location_map.insert(loc.clone(), locations);
         }
 
-        Ok(location_map
-            .into_iter()
-            .map(|(loc, resp)| (loc, resp.body.response))
-            .map(|(loc, restaurants)| {
-                let user_location = geoutils::Location::new(loc.lat, loc.long);
-                match restaurants {
-                    Some(r) => (
-                        loc.clone(),
-                        r.restaurants
-                            .iter()
-                            .map(|r| {
-                                let restaurant_location = geoutils::Location::new(
-                                    r.location.latitude,
-                                    r.location.longitude,
-                                );
-
-                                let distance = user_location
-                                    .distance_to(&restaurant_location)
-                                    .ok()
-                                    .map(|d| d.meters());
-
-                                Location {
-                                    name: r.name.clone(),
-                                    store_number: r.national_store_number.to_string(),
-                                    address: r.address.address_line1.clone(),
-                                    distance,
-                                }
-                            })
-                            .collect::<Vec<_>>(),
-                    ),
-                    None => (loc.clone(), vec![]),
-                }
-            })
-            .collect::<HashMap<_, _>>())
+        Ok(location_map)
     }
 }
 
 impl Loader<String> for LocationLoader {
-    type Value = Location;
+    type Value = stores::Model;
     type Error = Arc<anyhow::Error>;
 
     async fn load(&self, keys: &[String]) -> Result<HashMap<String, Self::Value>, Self::Error> {
@@ -128,25 +212,14 @@ impl Loader<String> for LocationLoader {
             .map_err(|e| Arc::new(e.into()))?
             .basic_auth(&self.settings.proxy.username, &self.settings.proxy.password);
 
-        let account_lock_txn = self
-            .database
-            .begin()
-            .await
-            .map_err(|e| Arc::new(e.into()))?;
-
         let api_client = maccas::get_activated_maccas_api_client(
             account_to_use,
             proxy,
             &self.settings.mcdonalds.client_id,
-            &account_lock_txn,
+            &self.database,
         )
         .await?;
 
-        account_lock_txn
-            .commit()
-            .await
-            .map_err(|e| Arc::new(e.into()))?;
-
         Ok(futures::future::try_join_all(
             keys.iter()
                 .map(|store_id| api_client.get_restaurant(store_id, FILTER, STORE_UNIQUE_ID_TYPE)),
@@ -155,17 +228,8 @@ impl Loader<String> for LocationLoader {
         .into_iter()
         .flatten()
         .filter_map(|r| r.body.response)
-        .map(|inner| {
-            (
-                inner.restaurant.national_store_number.to_string(),
-                Location {
-                    name: inner.restaurant.name,
-                    store_number: inner.restaurant.national_store_number.to_string(),
-                    address: inner.restaurant.address.address_line1,
-                    distance: None,
-                },
-            )
-        })
+        .flat_map(|inner| Database::convert_restaurant(&inner.restaurant))
+        .map(|model| (model.0.id.clone(), model.0))
         .collect::<HashMap<_, _>>())
     }
 }
@@ -0,0 +1,81 @@
+use crate::settings::{GeocoderProvider, Settings};
+use entity::stores;
+use places::{
+    Geocoder,
+    osm::{NOMINATIM_BASE_URL, NominatimClient, PHOTON_BASE_URL, PhotonClient},
+    stores::{StoreLocations, StoresGeocoder},
+    types::{ClientError, ClientResult, Location},
+};
+use reqwest_middleware::ClientWithMiddleware;
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect,
+    sea_query::{Expr, extension::postgres::PgExpr},
+};
+use std::sync::Arc;
+
+const MAX_MATCHED_STORES: u64 = 50;
+
+pub fn get_geocoder(
+    settings: &Settings,
+    http_client: ClientWithMiddleware,
+    database: DatabaseConnection,
+) -> Arc<dyn Geocoder> {
+    let base_url = settings.geocoder.base_url.clone();
+
+    match settings.geocoder.provider {
+        GeocoderProvider::Google => Arc::new(places::ApiClient::new(
+            settings.places_api_key.clone(),
+            http_client,
+        )),
+        GeocoderProvider::Nominatim => Arc::new(NominatimClient::new(
+            base_url.unwrap_or_else(|| NOMINATIM_BASE_URL.to_owned()),
+            http_client,
+        )),
+        GeocoderProvider::Photon => Arc::new(PhotonClient::new(
+            base_url.unwrap_or_else(|| PHOTON_BASE_URL.to_owned()),
+            http_client,
+        )),
+        GeocoderProvider::Stores => Arc::new(StoresGeocoder::new(SyncedStores { database })),
+    }
+}
+
+pub struct SyncedStores {
+    pub database: DatabaseConnection,
+}
+
+impl SyncedStores {
+    async fn locations(&self, condition: Condition) -> ClientResult<Vec<Location>> {
+        Ok(stores::Entity::find()
+            .filter(condition)
+            .limit(MAX_MATCHED_STORES)
+            .all(&self.database)
+            .await
+            .map_err(|e| ClientError::Backend(Box::new(e)))?
+            .into_iter()
+            .filter_map(|s| s.latitude.zip(s.longitude))
+            .map(|(latitude, longitude)| Location {
+                latitude,
+                longitude,
+            })
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl StoreLocations for SyncedStores {
+    async fn in_postcode(&self, postcode: &str) -> ClientResult<Vec<Location>> {
+        self.locations(Condition::all().add(stores::Column::Postcode.eq(postcode)))
+            .await
+    }
+
+    async fn in_suburb(&self, suburb: &str) -> ClientResult<Vec<Location>> {
+        // no wildcards, ILIKE is only here for the case insensitive match
+        let escaped = suburb
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+
+        self.locations(Condition::all().add(Expr::col(stores::Column::Suburb).ilike(escaped)))
+            .await
+    }
+}
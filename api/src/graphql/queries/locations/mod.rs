@@ -3,17 +3,16 @@ use self::types::{
 };
 use crate::graphql::queries::locations::dataloader::LocationLoader;
 use crate::graphql::queries::locations::types::LocationRequest;
-use crate::settings::Settings;
 use async_graphql::dataloader::DataLoader;
 use async_graphql::{Context, Object};
 use entity::stores;
-use places::types::Location as PlaceLocation;
-use places::types::{Area, PlacesRequest, Rectangle};
-use reqwest_middleware::ClientWithMiddleware;
-use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
+use places::Geocoder;
+use sea_orm::{DatabaseConnection, EntityTrait, IntoActiveModel, sea_query::OnConflict};
+use std::sync::Arc;
 
 pub mod dataloader;
-mod types;
+pub mod geocoder;
+pub mod types;
 
 #[derive(Default)]
 pub struct LocationsQuery;
@@ -25,8 +24,6 @@ impl LocationsQuery {
     }
 }
 
-// TODO: implement caching for locations (redis?)
-
 #[Object]
 impl QueriedLocation {
     async fn text<'a>(
@@ -34,41 +31,18 @@ impl QueriedLocation {
         ctx: &Context<'a>,
         input: TextSearchInput,
     ) -> async_graphql::Result<Vec<Location>> {
-        let settings = ctx.data::<Settings>()?;
-        let http_client = ctx.data::<ClientWithMiddleware>()?.clone();
-        let api_client = places::ApiClient::new(settings.places_api_key.clone(), http_client);
-
-        // Australia square, low -> high
-        // -46.2858922444765, 109.62638287960314
-        // -10.481731180947541, 156.54739153571109
-        let places_response = api_client
-            .get_place_by_text(&PlacesRequest {
-                text_query: input.query,
-                max_result_count: 1,
-                location_bias: Area {
-                    rectangle: Rectangle {
-                        low: PlaceLocation {
-                            latitude: -46.2858922444765,
-                            longitude: 109.62638287960314,
-                        },
-                        high: PlaceLocation {
-                            latitude: -10.481731180947541,
-                            longitude: 156.54739153571109,
-                        },
-                    },
-                },
-            })
-            .await?;
+        let geocoder = ctx.data::<Arc<dyn Geocoder>>()?;
+        let location = geocoder.geocode(&input.query).await?;
 
-        tracing::info!("locations found: {:?}", places_response.body);
+        tracing::info!("location found: {:?}", location);
 
-        match places_response.body.places.first() {
-            Some(response) => {
+        match location {
+            Some(location) => {
                 let loader = ctx.data::<DataLoader<LocationLoader>>()?;
                 match loader
                     .load_one(LocationRequest {
-                        lat: response.location.latitude,
-                        long: response.location.longitude,
+                        lat: location.latitude,
+                        long: location.longitude,
                     })
                     .await?
                 {
@@ -106,26 +80,37 @@ impl QueriedLocation {
         let db = ctx.data::<DatabaseConnection>()?;
 
         match stores::Entity::find_by_id(&input.store_id).one(db).await? {
-            Some(model) => Ok(Location {
-                name: model.name,
-                store_number: model.id,
-                address: model.address,
-                distance: None,
-            }),
-            None => {
+            // stores saved before details were tracked have no time zone, fetch them again
+            Some(model) if model.time_zone.is_some() => Ok(model.into()),
+            _ => {
                 let loader = ctx.data::<DataLoader<LocationLoader>>()?;
                 match loader.load_one(input.store_id).await? {
-                    Some(l) => {
-                        stores::ActiveModel {
-                            id: Set(l.store_number.clone()),
-                            name: Set(l.name.clone()),
-                            address: Set(l.address.clone()),
-                            ..Default::default()
-                        }
-                        .insert(db)
-                        .await?;
+                    Some(model) => {
+                        stores::Entity::insert(model.clone().into_active_model())
+                            .on_conflict(
+                                OnConflict::column(stores::Column::Id)
+                                    .update_columns([
+                                        stores::Column::Name,
+                                        stores::Column::Address,
+                                        stores::Column::Latitude,
+                                        stores::Column::Longitude,
+                                        stores::Column::TimeZone,
+                                        stores::Column::OpeningHours,
+                                        stores::Column::Facilities,
+                                        stores::Column::DriveThru,
+                                        stores::Column::McCafe,
+                                        stores::Column::Open24h,
+                                        stores::Column::TableService,
+                                        stores::Column::MobileOrder,
+                                        stores::Column::Suburb,
+                                        stores::Column::Postcode,
+                                    ])
+                                    .to_owned(),
+                            )
+                            .exec(db)
+                            .await?;
 
-                        Ok(l)
+                        Ok(model.into())
                     }
                     None => Err(anyhow::Error::msg("No store found for specified id").into()),
                 }
@@ -1,6 +1,10 @@
-use std::hash::Hasher;
+use std::{cmp::Ordering, hash::Hasher};
 
-use async_graphql::{InputObject, SimpleObject};
+use async_graphql::{InputObject, Object, SimpleObject};
+use chrono::{Datelike, Timelike, Weekday};
+use chrono_tz::Tz;
+use entity::stores;
+use libmaccas::types::response::WeekOpeningHour;
 
 pub struct QueriedLocation {}
 
@@ -21,13 +25,179 @@ pub struct StoreIdInput {
 }
 
 #[derive(SimpleObject, Clone)]
+pub struct ServiceHours {
+    pub service_name: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub is_open: bool,
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct OpeningHours {
+    pub day_of_week_id: i64,
+    pub services: Vec<ServiceHours>,
+}
+
+impl From<WeekOpeningHour> for OpeningHours {
+    fn from(hours: WeekOpeningHour) -> Self {
+        Self {
+            day_of_week_id: hours.day_of_week_id,
+            services: hours
+                .services
+                .into_iter()
+                .map(|s| ServiceHours {
+                    service_name: s.service_name,
+                    start_time: s.start_time,
+                    end_time: s.end_time,
+                    is_open: s.is_open,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Location {
-    pub name: String,
-    pub store_number: String,
-    pub address: String,
+    pub model: stores::Model,
     pub distance: Option<f64>,
 }
 
+impl From<stores::Model> for Location {
+    fn from(model: stores::Model) -> Self {
+        Self {
+            model,
+            distance: None,
+        }
+    }
+}
+
+impl Location {
+    fn week_opening_hours(&self) -> Vec<WeekOpeningHour> {
+        self.model
+            .opening_hours
+            .clone()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[Object]
+impl Location {
+    pub async fn name(&self) -> &String {
+        &self.model.name
+    }
+
+    pub async fn store_number(&self) -> &String {
+        &self.model.id
+    }
+
+    pub async fn address(&self) -> &String {
+        &self.model.address
+    }
+
+    pub async fn distance(&self) -> &Option<f64> {
+        &self.distance
+    }
+
+    pub async fn latitude(&self) -> &Option<f64> {
+        &self.model.latitude
+    }
+
+    pub async fn longitude(&self) -> &Option<f64> {
+        &self.model.longitude
+    }
+
+    pub async fn facilities(&self) -> &Vec<String> {
+        &self.model.facilities
+    }
+
+    pub async fn drive_thru(&self) -> &bool {
+        &self.model.drive_thru
+    }
+
+    pub async fn mc_cafe(&self) -> &bool {
+        &self.model.mc_cafe
+    }
+
+    #[graphql(name = "open24h")]
+    pub async fn open_24h(&self) -> &bool {
+        &self.model.open_24h
+    }
+
+    pub async fn table_service(&self) -> &bool {
+        &self.model.table_service
+    }
+
+    pub async fn mobile_order(&self) -> &bool {
+        &self.model.mobile_order
+    }
+
+    pub async fn opening_hours(&self) -> Vec<OpeningHours> {
+        self.week_opening_hours()
+            .into_iter()
+            .map(OpeningHours::from)
+            .collect()
+    }
+
+    // null when we don't have the store's time zone or hours to go off
+    pub async fn is_open_now(&self) -> Option<bool> {
+        if self.model.open_24h {
+            return Some(true);
+        }
+
+        let time_zone = self.model.time_zone.as_ref()?.parse::<Tz>().ok()?;
+        let hours = self.week_opening_hours();
+        if hours.is_empty() {
+            return None;
+        }
+
+        let now = chrono::Utc::now().with_timezone(&time_zone);
+        Some(is_open_at(
+            &hours,
+            now.weekday(),
+            now.hour() * 60 + now.minute(),
+        ))
+    }
+}
+
+// dayOfWeekId counts from monday = 1
+fn day_of_week_id(weekday: Weekday) -> i64 {
+    weekday.number_from_monday().into()
+}
+
+// "HH:MM" or "HH:MM:SS" as minutes past midnight, "24:00" is allowed as an end time
+fn parse_minutes(time: &str) -> Option<u32> {
+    let mut parts = time.split(':');
+    let hours = parts.next()?.trim().parse::<u32>().ok()?;
+    let minutes = parts.next()?.trim().parse::<u32>().ok()?;
+    (hours <= 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+fn is_open_at(hours: &[WeekOpeningHour], weekday: Weekday, minute_of_day: u32) -> bool {
+    let services_for = |weekday: Weekday| {
+        let id = day_of_week_id(weekday);
+        hours
+            .iter()
+            .filter(move |h| h.day_of_week_id == id)
+            .flat_map(|h| h.services.iter())
+            .filter(|s| s.is_open)
+            .filter_map(|s| Some((parse_minutes(&s.start_time)?, parse_minutes(&s.end_time)?)))
+    };
+
+    let open_today = services_for(weekday).any(|(start, end)| match start.cmp(&end) {
+        Ordering::Less => (start..end).contains(&minute_of_day),
+        // same start and end is open around the clock
+        Ordering::Equal => true,
+        Ordering::Greater => minute_of_day >= start,
+    });
+
+    // services that started yesterday and run past midnight
+    let open_from_yesterday =
+        services_for(weekday.pred()).any(|(start, end)| start > end && minute_of_day < end);
+
+    open_today || open_from_yesterday
+}
+
 #[derive(Clone)]
 pub struct LocationRequest {
     pub lat: f64,
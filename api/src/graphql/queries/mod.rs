@@ -1,5 +1,10 @@
+pub mod accounts;
 pub mod categories;
 pub mod health;
+pub mod history;
 pub mod locations;
+pub mod notifications;
 pub mod offers;
 pub mod points;
+pub mod products;
+pub mod stats;
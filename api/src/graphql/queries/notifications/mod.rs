@@ -0,0 +1,60 @@
+use self::types::NotificationRule;
+use crate::{graphql::ValidatedClaims, settings::Settings};
+use async_graphql::{Context, Object};
+use entity::{notification_rules, offer_favourites};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    prelude::Uuid,
+};
+
+pub mod types;
+
+#[derive(Default)]
+pub struct NotificationsQuery;
+
+#[Object]
+impl NotificationsQuery {
+    // the applicationServerKey browsers subscribe with, null when push is disabled
+    async fn web_push_public_key(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Option<String>> {
+        let settings = ctx.data::<Settings>()?;
+        Ok(settings
+            .web_push
+            .as_ref()
+            .map(|w| w.vapid_public_key.clone()))
+    }
+
+    async fn notification_rules(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Vec<NotificationRule>> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+
+        Ok(notification_rules::Entity::find()
+            .filter(notification_rules::Column::UserId.eq(user_id))
+            .order_by_asc(notification_rules::Column::Id)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(NotificationRule)
+            .collect())
+    }
+
+    // short names, favourites get a push before they expire
+    async fn favourite_offers(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<String>> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+
+        Ok(offer_favourites::Entity::find()
+            .select_only()
+            .column(offer_favourites::Column::ShortName)
+            .filter(offer_favourites::Column::UserId.eq(user_id))
+            .order_by_asc(offer_favourites::Column::ShortName)
+            .into_tuple::<String>()
+            .all(db)
+            .await?)
+    }
+}
@@ -0,0 +1,28 @@
+use async_graphql::Object;
+use entity::notification_rules;
+use sea_orm::prelude::DateTime;
+
+pub struct NotificationRule(pub notification_rules::Model);
+
+#[Object]
+impl NotificationRule {
+    pub async fn id(&self) -> &i32 {
+        &self.0.id
+    }
+
+    pub async fn categories(&self) -> &Vec<String> {
+        &self.0.categories
+    }
+
+    pub async fn keywords(&self) -> &Vec<String> {
+        &self.0.keywords
+    }
+
+    pub async fn similar_to_used(&self) -> &bool {
+        &self.0.similar_to_used
+    }
+
+    pub async fn created_at(&self) -> &DateTime {
+        &self.0.created_at
+    }
+}
@@ -1,15 +1,27 @@
 use crate::caching::{OfferDetailsCache, protos};
-use crate::{event_manager::EventManager, graphql::queries::offers::types::OfferCount, name_of};
+use crate::{
+    event_manager::EventManager,
+    graphql::queries::offers::types::{OfferCount, OfferHistory},
+    name_of,
+    settings::Settings,
+};
+use anyhow::Context as _;
 use api::{CreateEvent, Event};
 use async_graphql::dataloader::Loader;
+use base::constants::mc_donalds::OFFSET;
 use chrono::DateTime;
-use entity::{offer_details, offers};
+use entity::{accounts, offer_details, offers};
+use libmaccas::types::response::ProductSet;
 use opentelemetry::trace::TraceContextExt;
 use sea_orm::{
     ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, JoinType, QueryFilter,
-    QuerySelect, RelationTrait,
+    QuerySelect, RelationTrait, prelude::Uuid,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
-use std::{collections::HashMap, sync::Arc, time::Duration};
 use tracing::instrument;
 
 pub struct OfferDetailsLoader {
@@ -202,3 +214,153 @@ impl Loader<String> for OfferCountDataLoader {
             .collect::<HashMap<_, _>>())
     }
 }
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawProductSets {
+    product_sets: Vec<ProductSet>,
+}
+
+pub struct OfferProductSetsLoader {
+    pub database: DatabaseConnection,
+}
+
+impl Loader<i64> for OfferProductSetsLoader {
+    type Value = Vec<ProductSet>;
+    type Error = Arc<DbErr>;
+
+    // the offer details cache doesn't hold raw_data, so this always goes to the db
+    #[instrument(name = "OfferProductSetsLoader::load", skip(self, keys))]
+    async fn load(&self, keys: &[i64]) -> Result<HashMap<i64, Self::Value>, Self::Error> {
+        Ok(offer_details::Entity::find()
+            .select_only()
+            .column(offer_details::Column::PropositionId)
+            .column(offer_details::Column::RawData)
+            .filter(offer_details::Column::PropositionId.is_in(keys.iter().copied()))
+            .into_tuple::<(i64, Option<serde_json::Value>)>()
+            .all(&self.database)
+            .await?
+            .into_iter()
+            .filter_map(|(proposition_id, raw_data)| {
+                let raw_data = serde_json::from_value::<RawProductSets>(raw_data?)
+                    .inspect_err(|e| tracing::warn!("no product sets for {proposition_id}: {e}"))
+                    .ok()?;
+
+                Some((proposition_id, raw_data.product_sets))
+            })
+            .collect::<HashMap<_, _>>())
+    }
+}
+
+pub struct OfferHistoryLoader {
+    pub database: DatabaseConnection,
+}
+
+impl Loader<String> for OfferHistoryLoader {
+    type Value = OfferHistory;
+    type Error = Arc<DbErr>;
+
+    #[instrument(name = "OfferHistoryLoader::load", skip(self, keys))]
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Self::Value>, Self::Error> {
+        Ok(OfferHistory::load_many(&self.database, keys).await?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DealStackRequest {
+    pub account_id: Uuid,
+    pub store_id: String,
+}
+
+// codes are kept for a little while so clients polling activeDeals don't ask mcdonalds
+// for every deal on every poll
+const DEAL_STACK_CODE_TTL: Duration = Duration::from_secs(60);
+
+pub struct DealStackCodeLoader {
+    pub database: DatabaseConnection,
+    pub settings: Settings,
+    pub codes: Mutex<HashMap<DealStackRequest, (Instant, String)>>,
+}
+
+impl DealStackCodeLoader {
+    async fn fetch_code(
+        &self,
+        account: accounts::Model,
+        store_id: &str,
+    ) -> Result<String, anyhow::Error> {
+        let proxy = reqwest::Proxy::all(self.settings.proxy.url.clone())?
+            .basic_auth(&self.settings.proxy.username, &self.settings.proxy.password);
+
+        let api_client = base::maccas::get_activated_maccas_api_client(
+            account,
+            proxy,
+            &self.settings.mcdonalds.client_id,
+            &self.database,
+        )
+        .await?;
+
+        Ok(api_client
+            .get_offers_dealstack(OFFSET, store_id)
+            .await?
+            .body
+            .response
+            .context("must have deal stack response")?
+            .random_code)
+    }
+}
+
+impl Loader<DealStackRequest> for DealStackCodeLoader {
+    type Value = String;
+    type Error = Arc<DbErr>;
+
+    // a deal stack that can't be fetched is left out rather than failing the other deals
+    #[instrument(name = "DealStackCodeLoader::load", skip(self, keys))]
+    async fn load(
+        &self,
+        keys: &[DealStackRequest],
+    ) -> Result<HashMap<DealStackRequest, Self::Value>, Self::Error> {
+        let mut codes = {
+            let mut cached = self.codes.lock().unwrap_or_else(|e| e.into_inner());
+            cached.retain(|_, (fetched_at, _)| fetched_at.elapsed() < DEAL_STACK_CODE_TTL);
+
+            keys.iter()
+                .filter_map(|k| Some((k.clone(), cached.get(k)?.1.clone())))
+                .collect::<HashMap<_, _>>()
+        };
+
+        let missing = keys
+            .iter()
+            .filter(|k| !codes.contains_key(*k))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(codes);
+        }
+
+        let accounts = accounts::Entity::find()
+            .filter(accounts::Column::Id.is_in(missing.iter().map(|k| k.account_id)))
+            .all(&self.database)
+            .await?
+            .into_iter()
+            .map(|a| (a.id, a))
+            .collect::<HashMap<_, _>>();
+
+        let fetched = futures::future::join_all(missing.into_iter().filter_map(|key| {
+            let account = accounts.get(&key.account_id)?.clone();
+            Some(async move { (key, self.fetch_code(account, &key.store_id).await) })
+        }))
+        .await;
+
+        let mut cached = self.codes.lock().unwrap_or_else(|e| e.into_inner());
+        for (key, code) in fetched {
+            match code {
+                Ok(code) => {
+                    cached.insert(key.clone(), (Instant::now(), code.clone()));
+                    codes.insert(key.clone(), code);
+                }
+                Err(e) => tracing::warn!("error fetching deal stack for {key:?}: {e}"),
+            }
+        }
+
+        Ok(codes)
+    }
+}
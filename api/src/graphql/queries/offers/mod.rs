@@ -1,18 +1,72 @@
-use self::types::{Offer, OfferByIdInput, OfferByIdResponse};
+use self::dataloader::OfferHistoryLoader;
+use self::types::{
+    ActiveDeal, Offer, OfferByIdInput, OfferByIdResponse, OfferConnection, OfferCursor,
+    OfferFilter, OfferHistory, OfferPosition, OfferSort,
+};
 use crate::{graphql::ValidatedClaims, settings::Settings};
 use anyhow::Context as _;
-use async_graphql::{Context, Object};
+use async_graphql::{
+    Context, Object,
+    connection::{Connection, Edge, OpaqueCursor, query},
+    dataloader::DataLoader,
+};
 use base::constants::{MACCAS_ACCOUNT_REFRESH_FAILURE, mc_donalds::OFFSET};
-use entity::{accounts, offer_details, offers, recommendations};
+use entity::{
+    account_lock, accounts, active_transactions, offer_audit, offer_details, offers,
+    recommendations, sea_orm_active_enums::Action,
+};
 use sea_orm::{
-    ColumnTrait, Condition, DatabaseConnection, EntityTrait, JoinType, Order, QueryFilter,
-    QueryOrder, QuerySelect, RelationTrait, TransactionTrait, prelude::Uuid,
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    FromQueryResult, JoinType, Order, QueryFilter, QueryOrder, QuerySelect, QueryTrait,
+    RelationTrait, Value,
+    prelude::Uuid,
+    sea_query::{Alias, Asterisk, Expr, NullOrdering, Query},
 };
+use std::collections::HashMap;
 use std::str::FromStr;
 
 pub mod dataloader;
+mod search;
 mod types;
 
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 100;
+// how far back redemptions count towards popularity
+const POPULARITY_WINDOW: chrono::TimeDelta = chrono::TimeDelta::days(30);
+
+const MIN_COUNT_SQL: &str = r#"
+(
+    SELECT count(*)
+    FROM offers o
+    JOIN offer_details d ON d.proposition_id = o.offer_proposition_id
+    WHERE d.short_name = offer_details.short_name
+        AND o.valid_to > $1
+        AND o.valid_from < $1
+        AND o.account_id NOT IN (SELECT id FROM account_lock)
+) >= $2
+"#;
+
+const RECOMMENDATION_SCORE_SQL: &str = r#"
+-(
+    SELECT s.score
+    FROM offer_name_cluster_association n
+    JOIN offer_cluster_score s ON s.cluster_id = n.cluster_id
+    WHERE s.user_id = $1 AND n.name = offer_details.short_name
+    LIMIT 1
+)
+"#;
+
+const POPULARITY_SQL: &str = r#"
+-(
+    SELECT count(*)
+    FROM offer_audit a
+    JOIN offer_details d ON d.proposition_id = a.proposition_id
+    WHERE a.action = 'add'
+        AND a.created_at > $1
+        AND d.short_name = offer_details.short_name
+)::double precision
+"#;
+
 #[derive(Default)]
 pub struct OffersQuery;
 
@@ -38,15 +92,13 @@ impl OffersQuery {
         let proxy = reqwest::Proxy::all(settings.proxy.url.clone())?
             .basic_auth(&settings.proxy.username, &settings.proxy.password);
 
-        let account_lock_txn = db.begin().await?;
         let api_client = base::maccas::get_activated_maccas_api_client(
             account,
             proxy,
             &settings.mcdonalds.client_id,
-            &account_lock_txn,
+            db,
         )
         .await?;
-        account_lock_txn.commit().await?;
 
         let offer_code = api_client
             .get_offers_dealstack(OFFSET, &input.store_id)
@@ -105,6 +157,7 @@ impl OffersQuery {
         let now = chrono::offset::Utc::now().naive_utc();
 
         let conditions = conditions
+            .add(accounts::Column::Active.eq(true))
             .add(accounts::Column::RefreshFailureCount.lte(MACCAS_ACCOUNT_REFRESH_FAILURE))
             .add(offers::Column::ValidTo.gt(now))
             .add(offers::Column::ValidFrom.lt(now))
@@ -126,7 +179,11 @@ impl OffersQuery {
         Ok(offers)
     }
 
-    async fn offers(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Offer>> {
+    async fn offers(
+        &self,
+        ctx: &Context<'_>,
+        max_kilojoules: Option<f64>,
+    ) -> async_graphql::Result<Vec<Offer>> {
         let db = ctx.data::<DatabaseConnection>()?;
         let all_locked_accounts = entity::account_lock::Entity::find()
             .all(db)
@@ -142,11 +199,12 @@ impl OffersQuery {
         let now = chrono::offset::Utc::now().naive_utc();
 
         let conditions = conditions
+            .add(accounts::Column::Active.eq(true))
             .add(accounts::Column::RefreshFailureCount.lte(MACCAS_ACCOUNT_REFRESH_FAILURE))
             .add(offers::Column::ValidTo.gt(now))
             .add(offers::Column::ValidFrom.lt(now));
 
-        Ok(offers::Entity::find()
+        let offers = offers::Entity::find()
             .distinct_on([offer_details::Column::ShortName])
             .find_also_related(offer_details::Entity)
             .find_also_related(accounts::Entity)
@@ -157,6 +215,240 @@ impl OffersQuery {
             .await?
             .into_iter()
             .map(|(offer, _offer_details, _account)| Offer(offer))
-            .collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let Some(max_kilojoules) = max_kilojoules else {
+            return Ok(offers);
+        };
+
+        // offers without known nutrition can't be shown to match
+        let nutrition =
+            futures::future::try_join_all(offers.iter().map(|o| o.load_nutrition(ctx))).await?;
+
+        Ok(offers
+            .into_iter()
+            .zip(nutrition)
+            .filter(|(_, n)| n.as_ref().is_some_and(|n| n.kilojoules <= max_kilojoules))
+            .map(|(offer, _)| offer)
+            .collect())
+    }
+
+    async fn offers_connection(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] filter: OfferFilter,
+        sort: Option<OfferSort>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> async_graphql::Result<OfferConnection> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let locked_accounts = account_lock::Entity::find()
+            .select_only()
+            .column(account_lock::Column::Id)
+            .into_query();
+
+        let now = chrono::offset::Utc::now().naive_utc();
+
+        let mut conditions = filter
+            .conditions()
+            .add(offers::Column::AccountId.not_in_subquery(locked_accounts))
+            .add(accounts::Column::Active.eq(true))
+            .add(accounts::Column::RefreshFailureCount.lte(MACCAS_ACCOUNT_REFRESH_FAILURE))
+            .add(offers::Column::ValidTo.gt(now))
+            .add(offers::Column::ValidFrom.lt(now));
+
+        // same count as Offer::count, every unlocked copy regardless of the filter
+        if let Some(min_count) = filter.min_count {
+            conditions = conditions.add(Expr::cust_with_values(
+                MIN_COUNT_SQL,
+                [Value::from(now), min_count.into()],
+            ));
+        }
+
+        // ascending with nulls last, the descending sorts are negated to fit
+        let sort_key = match sort {
+            None => Expr::cust("NULL::double precision"),
+            Some(OfferSort::Price) => Expr::cust("offer_details.price"),
+            Some(OfferSort::Expiry) => {
+                Expr::cust("extract(epoch FROM offers.valid_to)::double precision")
+            }
+            Some(OfferSort::RecommendationScore) => {
+                let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+                Expr::cust_with_values(RECOMMENDATION_SCORE_SQL, [user_id])
+            }
+            Some(OfferSort::Popularity) => {
+                Expr::cust_with_values(POPULARITY_SQL, [now - POPULARITY_WINDOW])
+            }
+        };
+
+        // one offer per short name, the one expiring soonest
+        let candidates = offers::Entity::find()
+            .distinct_on([offer_details::Column::ShortName])
+            .join(JoinType::InnerJoin, offers::Relation::OfferDetails.def())
+            .join(JoinType::InnerJoin, offers::Relation::Accounts.def())
+            .column_as(offer_details::Column::ShortName, "short_name")
+            .column_as(sort_key, "sort_key")
+            .filter(conditions)
+            .order_by(offer_details::Column::ShortName, Order::Asc)
+            .order_by(offers::Column::ValidTo, Order::Asc)
+            .into_query();
+
+        query(
+            after,
+            None,
+            first,
+            None,
+            |after: Option<OfferCursor>, _, first, _| async move {
+                let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+                let mut page = Query::select();
+                page.column(Asterisk)
+                    .from_subquery(candidates, Alias::new("candidates"))
+                    .order_by_with_nulls(Alias::new("sort_key"), Order::Asc, NullOrdering::Last)
+                    .order_by(Alias::new("short_name"), Order::Asc)
+                    .limit(limit as u64 + 1);
+                if let Some(after) = &after {
+                    page.cond_where(after.0.after());
+                }
+
+                let mut rows = db
+                    .query_all(db.get_database_backend().build(&page))
+                    .await?
+                    .iter()
+                    .map(|row| {
+                        Ok((
+                            offers::Model::from_query_result(row, "")?,
+                            OfferPosition {
+                                sort_key: row.try_get("", "sort_key")?,
+                                short_name: row.try_get("", "short_name")?,
+                            },
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, DbErr>>()?;
+
+                let has_next_page = rows.len() > limit;
+                rows.truncate(limit);
+
+                let mut connection = Connection::new(after.is_some(), has_next_page);
+                connection.edges.extend(
+                    rows.into_iter()
+                        .map(|(offer, position)| Edge::new(OpaqueCursor(position), Offer(offer))),
+                );
+
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
+
+    // merges nearest neighbours from offer_embeddings with a full text match, either
+    // one is enough for an offer to show up
+    async fn search_offers(
+        &self,
+        ctx: &Context<'_>,
+        query: String,
+    ) -> async_graphql::Result<Vec<Offer>> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let api_client = ctx.data::<openai::ApiClient>()?;
+
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let (semantic, text) = futures::join!(
+            search::semantic_matches(db, api_client, query),
+            search::text_matches(db, query)
+        );
+
+        // keep working off the text ranking if openai is unavailable
+        let semantic = semantic
+            .inspect_err(|e| tracing::warn!("semantic offer search failed: {e}"))
+            .unwrap_or_default();
+        let short_names = search::fuse_rankings(&[semantic, text?]);
+
+        let locked_accounts = account_lock::Entity::find()
+            .select_only()
+            .column(account_lock::Column::Id)
+            .into_query();
+
+        let now = chrono::offset::Utc::now().naive_utc();
+
+        let conditions = Condition::all()
+            .add(offers::Column::AccountId.not_in_subquery(locked_accounts))
+            .add(accounts::Column::Active.eq(true))
+            .add(accounts::Column::RefreshFailureCount.lte(MACCAS_ACCOUNT_REFRESH_FAILURE))
+            .add(offers::Column::ValidTo.gt(now))
+            .add(offers::Column::ValidFrom.lt(now))
+            .add(offer_details::Column::ShortName.is_in(short_names.clone()));
+
+        let mut available = offers::Entity::find()
+            .distinct_on([offer_details::Column::ShortName])
+            .find_also_related(offer_details::Entity)
+            .find_also_related(accounts::Entity)
+            .order_by(offer_details::Column::ShortName, Order::Asc)
+            .order_by(offers::Column::ValidTo, Order::Asc)
+            .filter(conditions)
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|(offer, offer_details, _account)| {
+                Some((offer_details?.short_name, Offer(offer)))
+            })
+            .collect::<HashMap<_, _>>();
+
+        Ok(short_names
+            .iter()
+            .filter_map(|short_name| available.remove(short_name))
+            .collect())
+    }
+
+    async fn offer_history(
+        &self,
+        ctx: &Context<'_>,
+        short_name: String,
+    ) -> async_graphql::Result<OfferHistory> {
+        Ok(ctx
+            .data::<DataLoader<OfferHistoryLoader>>()?
+            .load_one(short_name)
+            .await?
+            .context("history is loaded for every short name")?)
+    }
+
+    // same rules as the deal limits: cleanup pending, not removed or used and the account
+    // still locked
+    async fn active_deals(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<ActiveDeal>> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+
+        let active = active_transactions::Entity::find()
+            .filter(active_transactions::Column::UserId.eq(user_id))
+            .order_by_asc(active_transactions::Column::UnlockAt)
+            .all(db)
+            .await?;
+
+        let store_ids = offer_audit::Entity::find()
+            .filter(
+                offer_audit::Column::TransactionId.is_in(active.iter().map(|a| a.transaction_id)),
+            )
+            .filter(offer_audit::Column::Action.eq(Action::Add))
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|a| Some((a.transaction_id, a.store_id?)))
+            .collect::<HashMap<_, _>>();
+
+        Ok(active
+            .into_iter()
+            .filter_map(|a| {
+                Some(ActiveDeal {
+                    transaction_id: a.transaction_id,
+                    offer_proposition_id: a.proposition_id,
+                    account_id: a.account_id,
+                    store_id: store_ids.get(&a.transaction_id)?.clone(),
+                    expires_at: a.unlock_at,
+                })
+            })
+            .collect())
     }
 }
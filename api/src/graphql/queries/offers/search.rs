@@ -0,0 +1,102 @@
+use anyhow::Context as _;
+use entity::{offer_details, offer_embeddings};
+use openai::types::OpenAIEmbeddingsRequest;
+use sea_orm::{
+    DatabaseConnection, DbErr, EntityTrait, Order, QueryFilter, QueryOrder, QuerySelect, Value,
+    prelude::{Expr, PgVector},
+};
+use std::collections::HashMap;
+
+// offer_embeddings were generated with this model, a query embedded with anything else
+// isn't comparable
+const EMBEDDING_MODEL: &str = "text-embedding-3-large";
+// must match offer_details_search_idx
+const SEARCH_DOCUMENT: &str = "to_tsvector('english', name || ' ' || description)";
+// candidates taken from each ranking before they're merged
+const CANDIDATES: u64 = 50;
+// nearest neighbours are returned however unrelated they are, anything further than this
+// from the query doesn't count as a match
+const MAX_COSINE_DISTANCE: f64 = 0.6;
+// reciprocal rank fusion constant, stops the top result of one ranking drowning out the other
+const RRF_K: f64 = 60.0;
+
+// short names closest to the query by cosine distance, within MAX_COSINE_DISTANCE
+pub async fn semantic_matches(
+    db: &DatabaseConnection,
+    api_client: &openai::ApiClient,
+    query: &str,
+) -> Result<Vec<String>, anyhow::Error> {
+    let request = OpenAIEmbeddingsRequest {
+        input: query.to_owned(),
+        model: EMBEDDING_MODEL.to_owned(),
+        dimensions: None,
+    };
+
+    let embedding = api_client
+        .embeddings(&request)
+        .await?
+        .body
+        .data
+        .pop()
+        .context("no embedding returned")?
+        .embedding;
+
+    let embedding = PgVector::from(embedding);
+
+    Ok(offer_embeddings::Entity::find()
+        .select_only()
+        .column(offer_embeddings::Column::Name)
+        .filter(Expr::cust_with_values(
+            "embeddings <=> $1 <= $2",
+            [Value::from(embedding.clone()), MAX_COSINE_DISTANCE.into()],
+        ))
+        .order_by(
+            Expr::cust_with_values("embeddings <=> $1", [embedding]),
+            Order::Asc,
+        )
+        .limit(CANDIDATES)
+        .into_tuple::<String>()
+        .all(db)
+        .await?)
+}
+
+// short names whose name or description match the query, best ts_rank first
+pub async fn text_matches(db: &DatabaseConnection, query: &str) -> Result<Vec<String>, DbErr> {
+    offer_details::Entity::find()
+        .select_only()
+        .column(offer_details::Column::ShortName)
+        .filter(Expr::cust_with_values(
+            format!("{SEARCH_DOCUMENT} @@ websearch_to_tsquery('english', $1)"),
+            [query],
+        ))
+        .group_by(offer_details::Column::ShortName)
+        .order_by(
+            Expr::cust_with_values(
+                format!("MAX(ts_rank({SEARCH_DOCUMENT}, websearch_to_tsquery('english', $1)))"),
+                [query],
+            ),
+            Order::Desc,
+        )
+        .limit(CANDIDATES)
+        .into_tuple::<String>()
+        .all(db)
+        .await
+}
+
+// the scores aren't comparable between rankings, so only each result's position counts
+pub fn fuse_rankings(rankings: &[Vec<String>]) -> Vec<String> {
+    let mut scores = HashMap::<&str, f64>::new();
+    for ranking in rankings {
+        for (rank, short_name) in ranking.iter().enumerate() {
+            *scores.entry(short_name).or_default() += 1.0 / (RRF_K + rank as f64 + 1.0);
+        }
+    }
+
+    let mut fused = scores.into_iter().collect::<Vec<_>>();
+    fused.sort_by(|(a_name, a), (b_name, b)| b.total_cmp(a).then_with(|| a_name.cmp(b_name)));
+
+    fused
+        .into_iter()
+        .map(|(short_name, _)| short_name.to_owned())
+        .collect()
+}
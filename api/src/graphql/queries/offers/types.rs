@@ -1,28 +1,51 @@
 use std::str::FromStr;
 
+use super::dataloader::DealStackCodeLoader;
+use super::dataloader::DealStackRequest;
 use super::dataloader::OfferCountDataLoader;
 use super::dataloader::OfferDetailsLoader;
+use super::dataloader::OfferHistoryLoader;
+use super::dataloader::OfferProductSetsLoader;
 use crate::graphql::ValidatedClaims;
+use crate::graphql::queries::locations::types::Location;
+use crate::graphql::queries::products::dataloader::{
+    ProductLoader, ProductPriceLoader, ProductPriceRequest,
+};
 use anyhow::Context;
+use async_graphql::connection::{Connection, OpaqueCursor};
 use async_graphql::dataloader::*;
+use async_graphql::Enum;
 use async_graphql::InputObject;
 use async_graphql::Object;
 use async_graphql::SimpleObject;
 use base::constants::IMAGE_BASE_URL;
 use base::constants::IMAGE_EXT;
+use chrono::Utc;
 use entity::offer_cluster_score;
 use entity::offer_details;
+use entity::offer_history;
 use entity::offer_name_cluster_association;
 use entity::offers;
+use entity::products;
+use entity::stores;
+use libmaccas::types::response::{DiscountType, ProductSet};
 use sea_orm::prelude::{DateTime, Uuid};
+use sea_orm::sea_query::extension::postgres::{PgBinOper, PgExpr};
+use sea_orm::sea_query::{Alias, Expr, Func};
 use sea_orm::ColumnTrait;
+use sea_orm::Condition;
 use sea_orm::DatabaseConnection;
+use sea_orm::DbErr;
 use sea_orm::EntityTrait;
 use sea_orm::FromQueryResult;
 use sea_orm::JoinType;
 use sea_orm::QueryFilter;
+use sea_orm::QueryOrder;
 use sea_orm::QuerySelect;
+use sea_orm::RelationTrait;
 use sea_orm::SelectColumns;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(InputObject)]
 pub struct OfferByIdInput {
@@ -35,6 +58,223 @@ pub struct OfferByIdResponse {
     pub code: String,
 }
 
+#[derive(InputObject, Default)]
+pub struct OfferFilter {
+    // matches offers in any of these categories
+    pub categories: Option<Vec<String>>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    // case insensitive match on the name or description
+    pub text: Option<String>,
+    pub valid_until_after: Option<DateTime>,
+    pub valid_until_before: Option<DateTime>,
+    pub min_count: Option<i64>,
+}
+
+impl OfferFilter {
+    pub fn conditions(&self) -> Condition {
+        let mut conditions = Condition::all();
+
+        if let Some(categories) = self.categories.clone() {
+            conditions = conditions.add(
+                Expr::col((offer_details::Entity, offer_details::Column::Categories))
+                    .binary(PgBinOper::Overlap, Expr::val(categories)),
+            );
+        }
+        if let Some(min_price) = self.min_price {
+            conditions = conditions.add(offer_details::Column::Price.gte(min_price));
+        }
+        if let Some(max_price) = self.max_price {
+            conditions = conditions.add(offer_details::Column::Price.lte(max_price));
+        }
+        let text = self.text.as_deref().unwrap_or_default().trim();
+        if !text.is_empty() {
+            let escaped = text
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            let pattern = format!("%{escaped}%");
+
+            conditions = conditions.add(
+                Condition::any()
+                    .add(
+                        Expr::col((offer_details::Entity, offer_details::Column::Name))
+                            .ilike(pattern.clone()),
+                    )
+                    .add(
+                        Expr::col((offer_details::Entity, offer_details::Column::Description))
+                            .ilike(pattern),
+                    ),
+            );
+        }
+        if let Some(after) = self.valid_until_after {
+            conditions = conditions.add(offers::Column::ValidTo.gte(after));
+        }
+        if let Some(before) = self.valid_until_before {
+            conditions = conditions.add(offers::Column::ValidTo.lte(before));
+        }
+
+        conditions
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum OfferSort {
+    // cheapest first
+    Price,
+    // soonest to expire first
+    Expiry,
+    // highest score for the current user first
+    RecommendationScore,
+    // most redeemed over the last month first
+    Popularity,
+}
+
+// where the last offer sat in the sort, short name breaks ties and sort_key is None for
+// offers sorted last because they have no key
+#[derive(Serialize, Deserialize)]
+pub struct OfferPosition {
+    pub sort_key: Option<f64>,
+    pub short_name: String,
+}
+
+impl OfferPosition {
+    // everything sorted after this position
+    pub fn after(&self) -> Condition {
+        let sort_key = Expr::col(Alias::new("sort_key"));
+        let short_name = Expr::col(Alias::new("short_name"));
+
+        match self.sort_key {
+            Some(key) => Condition::any()
+                .add(sort_key.clone().gt(key))
+                .add(
+                    Condition::all()
+                        .add(sort_key.clone().eq(key))
+                        .add(short_name.gt(self.short_name.clone())),
+                )
+                .add(sort_key.is_null()),
+            None => Condition::all()
+                .add(sort_key.is_null())
+                .add(short_name.gt(self.short_name.clone())),
+        }
+    }
+}
+
+pub type OfferCursor = OpaqueCursor<OfferPosition>;
+pub type OfferConnection = Connection<OfferCursor, Offer>;
+
+#[derive(SimpleObject)]
+pub struct OfferSavings {
+    pub original_price: f64,
+    pub discounted_price: f64,
+    pub savings: f64,
+}
+
+impl OfferSavings {
+    // None when any product set has no menu price at this store or an unknown discount type.
+    // user_deal_rollup's estimated_savings does the same in sql, keep the two in step
+    pub fn calculate(product_sets: &[ProductSet], menu_prices: &HashMap<i64, f64>) -> Option<Self> {
+        if product_sets.is_empty() {
+            return None;
+        }
+
+        let mut original_price = 0f64;
+        let mut discounted_price = 0f64;
+
+        for product_set in product_sets {
+            // any of the products satisfies the set, compare against the cheapest
+            let menu_price = product_set
+                .products
+                .iter()
+                .filter_map(|p| p.parse::<i64>().ok())
+                .filter_map(|p| menu_prices.get(&p).copied())
+                .reduce(f64::min)?;
+
+            let quantity = product_set.quantity.max(1) as f64;
+            let set_price = menu_price * quantity;
+
+            original_price += set_price;
+            discounted_price += match &product_set.action {
+                None => set_price,
+                // same as offer_details.price
+                Some(action) => match action.discount()? {
+                    DiscountType::PricePoint => action.value * quantity,
+                },
+            };
+        }
+
+        let round = |v: f64| (v * 100.0).round() / 100.0;
+
+        Some(Self {
+            original_price: round(original_price),
+            discounted_price: round(discounted_price),
+            savings: round(original_price - discounted_price),
+        })
+    }
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct OfferNutrition {
+    pub kilojoules: f64,
+    pub kilocalories: Option<f64>,
+    pub protein: Option<f64>,
+    pub fat: Option<f64>,
+    pub carbohydrates: Option<f64>,
+    pub sodium: Option<f64>,
+}
+
+impl OfferNutrition {
+    // None unless every product set resolves to a product with known energy
+    pub fn calculate(
+        product_sets: &[ProductSet],
+        products: &HashMap<i64, products::Model>,
+    ) -> Option<Self> {
+        if product_sets.is_empty() {
+            return None;
+        }
+
+        let mut nutrition = Self {
+            kilojoules: 0.0,
+            kilocalories: Some(0.0),
+            protein: Some(0.0),
+            fat: Some(0.0),
+            carbohydrates: Some(0.0),
+            sodium: Some(0.0),
+        };
+
+        for product_set in product_sets {
+            let product = product_set
+                .products
+                .iter()
+                .filter_map(|p| p.parse::<i64>().ok())
+                .find_map(|p| products.get(&p))?;
+
+            let quantity = product_set.quantity.max(1) as f64;
+            // only a total when every product has the nutrient
+            let add = |total: Option<f64>, key: &str| {
+                Some(total? + Self::nutrient(product, key)? * quantity)
+            };
+
+            nutrition.kilojoules += product.kilojoules? * quantity;
+            nutrition.kilocalories = add(nutrition.kilocalories, "KCal");
+            nutrition.protein = add(nutrition.protein, "Protein");
+            nutrition.fat = add(nutrition.fat, "Totalfat");
+            nutrition.carbohydrates = add(nutrition.carbohydrates, "Carbohydrates");
+            nutrition.sodium = add(nutrition.sodium, "Sodium");
+        }
+
+        Some(nutrition)
+    }
+
+    // values in the catalog are a mix of numbers and numeric strings
+    fn nutrient(product: &products::Model, key: &str) -> Option<f64> {
+        let value = product.nutrition.as_ref()?.get(key)?;
+        value
+            .as_f64()
+            .or_else(|| value.as_str()?.trim().parse::<f64>().ok())
+    }
+}
+
 #[derive(FromQueryResult, Debug)]
 pub struct OfferCount {
     pub short_name: String,
@@ -60,6 +300,35 @@ impl Offer {
             .map(mapping)
             .ok_or(anyhow::Error::msg("no name found for this offer").into())
     }
+
+    async fn load_product_sets(
+        &self,
+        context: &async_graphql::Context<'_>,
+    ) -> async_graphql::Result<Vec<ProductSet>> {
+        Ok(context
+            .data::<DataLoader<OfferProductSetsLoader>>()?
+            .load_one(self.0.offer_proposition_id)
+            .await?
+            .unwrap_or_default())
+    }
+
+    pub async fn load_nutrition(
+        &self,
+        context: &async_graphql::Context<'_>,
+    ) -> async_graphql::Result<Option<OfferNutrition>> {
+        let product_sets = self.load_product_sets(context).await?;
+        let product_codes = product_sets
+            .iter()
+            .flat_map(|s| s.products.iter())
+            .filter_map(|p| p.parse::<i64>().ok());
+
+        let products = context
+            .data::<DataLoader<ProductLoader>>()?
+            .load_many(product_codes)
+            .await?;
+
+        Ok(OfferNutrition::calculate(&product_sets, &products))
+    }
 }
 
 #[Object]
@@ -178,4 +447,307 @@ impl Offer {
     ) -> async_graphql::Result<Option<f64>> {
         self.load_from_related_offer(context, |o| o.price).await
     }
+
+    pub async fn savings(
+        &self,
+        context: &async_graphql::Context<'_>,
+        store_id: String,
+    ) -> async_graphql::Result<Option<OfferSavings>> {
+        let product_sets = self.load_product_sets(context).await?;
+
+        let requests = product_sets
+            .iter()
+            .flat_map(|s| s.products.iter())
+            .filter_map(|p| p.parse::<i64>().ok())
+            .map(|product_code| ProductPriceRequest {
+                store_id: store_id.clone(),
+                product_code,
+            });
+
+        let menu_prices = context
+            .data::<DataLoader<ProductPriceLoader>>()?
+            .load_many(requests)
+            .await?
+            .into_iter()
+            .map(|(request, price)| (request.product_code, price))
+            .collect::<HashMap<_, _>>();
+
+        Ok(OfferSavings::calculate(&product_sets, &menu_prices))
+    }
+
+    pub async fn nutrition(
+        &self,
+        context: &async_graphql::Context<'_>,
+    ) -> async_graphql::Result<Option<OfferNutrition>> {
+        self.load_nutrition(context).await
+    }
+
+    pub async fn history(
+        &self,
+        context: &async_graphql::Context<'_>,
+    ) -> async_graphql::Result<OfferHistory> {
+        let short_name = self.short_name(context).await?;
+
+        Ok(context
+            .data::<DataLoader<OfferHistoryLoader>>()?
+            .load_one(short_name)
+            .await?
+            .context("history is loaded for every short name")?)
+    }
+}
+
+// a validity window the deal was handed out with, refreshes that see it again extend
+// last_seen_at
+#[derive(SimpleObject, Clone)]
+pub struct OfferAppearance {
+    pub valid_from: DateTime,
+    pub valid_to: DateTime,
+    pub first_seen_at: DateTime,
+    pub last_seen_at: DateTime,
+    pub account_count: i64,
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct OfferPriceChange {
+    pub offer_proposition_id: i64,
+    pub price: Option<f64>,
+    pub changed_at: DateTime,
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct OfferHistory {
+    pub short_name: String,
+    pub appearances: Vec<OfferAppearance>,
+    pub price_changes: Vec<OfferPriceChange>,
+}
+
+impl OfferHistory {
+    // every proposition sharing the short name counts as the same deal, names without any
+    // history still get an empty entry
+    pub async fn load_many(
+        db: &DatabaseConnection,
+        short_names: &[String],
+    ) -> Result<HashMap<String, Self>, DbErr> {
+        let mut histories = short_names
+            .iter()
+            .map(|short_name| {
+                (
+                    short_name.clone(),
+                    Self {
+                        short_name: short_name.clone(),
+                        appearances: vec![],
+                        price_changes: vec![],
+                    },
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        let appearances = offer_history::Entity::find()
+            .select_only()
+            .column(offer_details::Column::ShortName)
+            .column(offer_history::Column::ValidFrom)
+            .column(offer_history::Column::ValidTo)
+            .column_as(offer_history::Column::CreatedAt.min(), "first_seen_at")
+            .column_as(offer_history::Column::CreatedAt.max(), "last_seen_at")
+            .column_as(
+                Expr::expr(Func::count_distinct(Expr::col((
+                    offer_history::Entity,
+                    offer_history::Column::AccountId,
+                )))),
+                "account_count",
+            )
+            .join(
+                JoinType::InnerJoin,
+                offer_history::Relation::OfferDetails.def(),
+            )
+            .filter(offer_details::Column::ShortName.is_in(short_names))
+            .group_by(offer_details::Column::ShortName)
+            .group_by(offer_history::Column::ValidFrom)
+            .group_by(offer_history::Column::ValidTo)
+            .order_by_asc(offer_history::Column::ValidFrom)
+            .into_tuple::<(String, DateTime, DateTime, DateTime, DateTime, i64)>()
+            .all(db)
+            .await?;
+
+        for (short_name, valid_from, valid_to, first_seen_at, last_seen_at, account_count) in
+            appearances
+        {
+            if let Some(history) = histories.get_mut(&short_name) {
+                history.appearances.push(OfferAppearance {
+                    valid_from,
+                    valid_to,
+                    first_seen_at,
+                    last_seen_at,
+                    account_count,
+                });
+            }
+        }
+
+        // each proposition is a snapshot of the deal, only keep the ones that moved the price
+        let snapshots = offer_details::Entity::find()
+            .filter(offer_details::Column::ShortName.is_in(short_names))
+            .order_by_asc(offer_details::Column::CreatedAt)
+            .all(db)
+            .await?;
+        for snapshot in snapshots {
+            let Some(history) = histories.get_mut(&snapshot.short_name) else {
+                continue;
+            };
+
+            if history
+                .price_changes
+                .last()
+                .is_some_and(|last| last.price == snapshot.price)
+            {
+                continue;
+            }
+
+            history.price_changes.push(OfferPriceChange {
+                offer_proposition_id: snapshot.proposition_id,
+                price: snapshot.price,
+                changed_at: snapshot.created_at,
+            });
+        }
+
+        Ok(histories)
+    }
+}
+
+// a deal in the user's deal stack, until its Cleanup event runs
+pub struct ActiveDeal {
+    pub transaction_id: Uuid,
+    pub offer_proposition_id: i64,
+    pub account_id: Uuid,
+    pub store_id: String,
+    pub expires_at: DateTime,
+}
+
+#[Object]
+impl ActiveDeal {
+    pub async fn transaction_id(&self) -> &Uuid {
+        &self.transaction_id
+    }
+
+    pub async fn offer_proposition_id(&self) -> &i64 {
+        &self.offer_proposition_id
+    }
+
+    pub async fn expires_at(&self) -> chrono::DateTime<Utc> {
+        self.expires_at.and_utc()
+    }
+
+    // offer ids change whenever the account is refreshed
+    pub async fn offer(
+        &self,
+        context: &async_graphql::Context<'_>,
+    ) -> async_graphql::Result<Option<Offer>> {
+        let db = context.data::<DatabaseConnection>()?;
+
+        Ok(offers::Entity::find()
+            .filter(offers::Column::AccountId.eq(self.account_id))
+            .filter(offers::Column::OfferPropositionId.eq(self.offer_proposition_id))
+            .one(db)
+            .await?
+            .map(Offer))
+    }
+
+    pub async fn store(
+        &self,
+        context: &async_graphql::Context<'_>,
+    ) -> async_graphql::Result<Option<Location>> {
+        let db = context.data::<DatabaseConnection>()?;
+
+        Ok(stores::Entity::find_by_id(self.store_id.clone())
+            .one(db)
+            .await?
+            .map(Location::from))
+    }
+
+    // the code isn't stored, the deal stack is asked for it again (briefly cached)
+    pub async fn code(
+        &self,
+        context: &async_graphql::Context<'_>,
+    ) -> async_graphql::Result<String> {
+        Ok(context
+            .data::<DataLoader<DealStackCodeLoader>>()?
+            .load_one(DealStackRequest {
+                account_id: self.account_id,
+                store_id: self.store_id.clone(),
+            })
+            .await?
+            .context("must have deal stack response")?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OfferSavings;
+    use libmaccas::types::response::{Action, ProductSet};
+    use std::collections::HashMap;
+
+    fn product_set(products: &[&str], quantity: i64, action: Option<Action>) -> ProductSet {
+        ProductSet {
+            products: products.iter().map(|p| p.to_string()).collect(),
+            quantity,
+            action,
+            ..Default::default()
+        }
+    }
+
+    fn price_point(value: f64) -> Action {
+        Action {
+            type_field: 3,
+            discount_type: 2,
+            value,
+        }
+    }
+
+    #[test]
+    fn price_point_against_cheapest_product() {
+        // 2 hash browns for $1 each, as in converters/src/offers.rs
+        let product_sets = [product_set(&["202", "203"], 2, Some(price_point(1.0)))];
+        let menu_prices = HashMap::from([(202, 2.6), (203, 2.9)]);
+
+        let savings = OfferSavings::calculate(&product_sets, &menu_prices).unwrap();
+
+        assert_eq!(savings.original_price, 5.2);
+        assert_eq!(savings.discounted_price, 2.0);
+        assert_eq!(savings.savings, 3.2);
+    }
+
+    #[test]
+    fn sets_without_an_action_are_full_price() {
+        let product_sets = [
+            product_set(&["1"], 1, None),
+            product_set(&["2"], 1, Some(price_point(0.0))),
+        ];
+        let menu_prices = HashMap::from([(1, 7.45), (2, 3.0)]);
+
+        let savings = OfferSavings::calculate(&product_sets, &menu_prices).unwrap();
+
+        assert_eq!(savings.original_price, 10.45);
+        assert_eq!(savings.discounted_price, 7.45);
+        assert_eq!(savings.savings, 3.0);
+    }
+
+    #[test]
+    fn unknown_discount_type_has_no_savings() {
+        let action = Action {
+            type_field: 3,
+            discount_type: 0,
+            value: 1.0,
+        };
+        let product_sets = [product_set(&["1"], 1, Some(action))];
+        let menu_prices = HashMap::from([(1, 5.0)]);
+
+        assert!(OfferSavings::calculate(&product_sets, &menu_prices).is_none());
+    }
+
+    #[test]
+    fn missing_menu_price_has_no_savings() {
+        let product_sets = [product_set(&["1"], 1, Some(price_point(1.0)))];
+
+        assert!(OfferSavings::calculate(&product_sets, &HashMap::new()).is_none());
+        assert!(OfferSavings::calculate(&[], &HashMap::from([(1, 5.0)])).is_none());
+    }
 }
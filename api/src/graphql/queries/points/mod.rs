@@ -25,6 +25,7 @@ impl PointsQuery {
         Ok(points::Entity::find()
             .find_also_related(accounts::Entity)
             .order_by(points::Column::CurrentPoints, Order::Asc)
+            .filter(accounts::Column::Active.eq(true))
             .filter(accounts::Column::RefreshFailureCount.lte(MACCAS_ACCOUNT_REFRESH_FAILURE))
             .filter(
                 points::Column::CurrentPoints
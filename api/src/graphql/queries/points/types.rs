@@ -4,7 +4,7 @@ use async_graphql::{InputObject, Object};
 use base::constants::mc_donalds::OFFSET;
 use entity::{accounts, points};
 use opentelemetry::trace::TraceContextExt;
-use sea_orm::{DatabaseConnection, EntityTrait, TransactionTrait, prelude::Uuid};
+use sea_orm::{DatabaseConnection, EntityTrait, prelude::Uuid};
 use std::time::Duration;
 
 #[derive(InputObject)]
@@ -52,15 +52,13 @@ impl Points {
         let proxy = reqwest::Proxy::all(settings.proxy.url.clone())?
             .basic_auth(&settings.proxy.username, &settings.proxy.password);
 
-        let account_lock_txn = db.begin().await?;
         let api_client = base::maccas::get_activated_maccas_api_client(
             account_to_use,
             proxy,
             &settings.mcdonalds.client_id,
-            &account_lock_txn,
+            db,
         )
         .await?;
-        account_lock_txn.commit().await?;
 
         let code_response = api_client
             .get_offers_dealstack(OFFSET, self.store_id.as_ref().unwrap())
@@ -0,0 +1,68 @@
+use async_graphql::dataloader::Loader;
+use entity::{product_prices, products};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use std::{collections::HashMap, sync::Arc};
+use tracing::instrument;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ProductPriceRequest {
+    pub store_id: String,
+    pub product_code: i64,
+}
+
+pub struct ProductPriceLoader {
+    pub database: DatabaseConnection,
+}
+
+impl Loader<ProductPriceRequest> for ProductPriceLoader {
+    // lowest menu price across price types
+    type Value = f64;
+    type Error = Arc<DbErr>;
+
+    #[instrument(name = "ProductPriceLoader::load", skip(self, keys))]
+    async fn load(
+        &self,
+        keys: &[ProductPriceRequest],
+    ) -> Result<HashMap<ProductPriceRequest, Self::Value>, Self::Error> {
+        let prices = product_prices::Entity::find()
+            .filter(product_prices::Column::StoreId.is_in(keys.iter().map(|k| k.store_id.clone())))
+            .filter(product_prices::Column::ProductCode.is_in(keys.iter().map(|k| k.product_code)))
+            .all(&self.database)
+            .await?;
+
+        let mut result = HashMap::<ProductPriceRequest, f64>::new();
+        for price in prices {
+            let key = ProductPriceRequest {
+                store_id: price.store_id,
+                product_code: price.product_code,
+            };
+
+            result
+                .entry(key)
+                .and_modify(|p| *p = p.min(price.price))
+                .or_insert(price.price);
+        }
+
+        Ok(result)
+    }
+}
+
+pub struct ProductLoader {
+    pub database: DatabaseConnection,
+}
+
+impl Loader<i64> for ProductLoader {
+    type Value = products::Model;
+    type Error = Arc<DbErr>;
+
+    #[instrument(name = "ProductLoader::load", skip(self, keys))]
+    async fn load(&self, keys: &[i64]) -> Result<HashMap<i64, Self::Value>, Self::Error> {
+        Ok(products::Entity::find()
+            .filter(products::Column::ProductCode.is_in(keys.iter().copied()))
+            .all(&self.database)
+            .await?
+            .into_iter()
+            .map(|p| (p.product_code, p))
+            .collect::<HashMap<_, _>>())
+    }
+}
@@ -0,0 +1,67 @@
+use self::types::{Product, ProductFilterInput};
+use async_graphql::{Context, Object};
+use entity::{product_prices, products};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, Order, QueryFilter, QueryOrder,
+    prelude::Expr, sea_query::extension::postgres::PgExpr,
+};
+use std::collections::HashMap;
+
+pub mod dataloader;
+mod types;
+
+#[derive(Default)]
+pub struct ProductsQuery;
+
+#[Object]
+impl ProductsQuery {
+    async fn products<'a>(
+        &self,
+        ctx: &Context<'a>,
+        filter: Option<ProductFilterInput>,
+    ) -> async_graphql::Result<Vec<Product>> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let filter = filter.unwrap_or_default();
+
+        let mut conditions = Condition::all().add(products::Column::IsSalable.eq(true));
+        if let Some(name) = &filter.name {
+            let escaped = name
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+
+            conditions =
+                conditions.add(Expr::col(products::Column::Name).ilike(format!("%{escaped}%")));
+        }
+
+        let products = products::Entity::find()
+            .filter(conditions)
+            .order_by(products::Column::Name, Order::Asc)
+            .all(db)
+            .await?;
+
+        let mut price_conditions = Condition::all().add(
+            product_prices::Column::ProductCode.is_in(products.iter().map(|p| p.product_code)),
+        );
+        if let Some(store_id) = &filter.store_id {
+            price_conditions = price_conditions.add(product_prices::Column::StoreId.eq(store_id));
+        }
+
+        let mut prices = HashMap::<i64, Vec<product_prices::Model>>::new();
+        for price in product_prices::Entity::find()
+            .filter(price_conditions)
+            .all(db)
+            .await?
+        {
+            prices.entry(price.product_code).or_default().push(price);
+        }
+
+        Ok(products
+            .into_iter()
+            .map(|model| Product {
+                prices: prices.remove(&model.product_code).unwrap_or_default(),
+                model,
+            })
+            .collect())
+    }
+}
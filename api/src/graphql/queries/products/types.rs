@@ -0,0 +1,73 @@
+use async_graphql::{InputObject, Json, Object, SimpleObject};
+use entity::{product_prices, products};
+
+#[derive(InputObject, Default)]
+pub struct ProductFilterInput {
+    pub name: Option<String>,
+    pub store_id: Option<String>,
+}
+
+#[derive(SimpleObject)]
+pub struct ProductPrice {
+    pub store_id: String,
+    pub price_type_id: i64,
+    pub price: f64,
+}
+
+impl From<product_prices::Model> for ProductPrice {
+    fn from(model: product_prices::Model) -> Self {
+        Self {
+            store_id: model.store_id,
+            price_type_id: model.price_type_id,
+            price: model.price,
+        }
+    }
+}
+
+pub struct Product {
+    pub model: products::Model,
+    pub prices: Vec<product_prices::Model>,
+}
+
+#[Object]
+impl Product {
+    pub async fn product_code(&self) -> &i64 {
+        &self.model.product_code
+    }
+
+    pub async fn name(&self) -> &String {
+        &self.model.name
+    }
+
+    pub async fn short_name(&self) -> &Option<String> {
+        &self.model.short_name
+    }
+
+    pub async fn image_name(&self) -> &String {
+        &self.model.image_name
+    }
+
+    pub async fn kilojoules(&self) -> &Option<f64> {
+        &self.model.kilojoules
+    }
+
+    pub async fn nutrition(&self) -> Option<Json<serde_json::Value>> {
+        self.model.nutrition.clone().map(Json)
+    }
+
+    pub async fn category_ids(&self) -> &Vec<i32> {
+        &self.model.category_ids
+    }
+
+    pub async fn is_promotional(&self) -> &bool {
+        &self.model.is_promotional
+    }
+
+    pub async fn prices(&self) -> Vec<ProductPrice> {
+        self.prices
+            .iter()
+            .cloned()
+            .map(ProductPrice::from)
+            .collect()
+    }
+}
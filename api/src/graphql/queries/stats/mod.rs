@@ -0,0 +1,205 @@
+use self::types::{
+    DealBucket, MyStats, OfferDeals, OfferUsage, StatsRange, StoreDeals, StoreUsage, Streak, Totals,
+};
+use crate::graphql::ValidatedClaims;
+use anyhow::Context as _;
+use async_graphql::{Context, Object};
+use chrono::Days;
+use chrono_tz::Tz;
+use entity::{offer_details, stores, user_deal_rollup};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, DbBackend, EntityTrait, FromQueryResult, Order,
+    QueryFilter, QueryOrder, QuerySelect, Statement, prelude::Uuid, sea_query::Expr,
+};
+use std::collections::HashMap;
+
+mod types;
+
+const DEFAULT_TIME_ZONE: &str = "Australia/Sydney";
+const TOP_OFFERS: u64 = 5;
+
+const STREAKS_SQL: &str = r#"
+WITH days AS (
+    SELECT DISTINCT (hour AT TIME ZONE 'UTC' AT TIME ZONE $2)::date AS day
+    FROM user_deal_rollup
+    WHERE user_id = $1 AND hour >= $3
+), streaks AS (
+    SELECT day, day - (row_number() OVER (ORDER BY day))::int AS streak
+    FROM days
+)
+SELECT count(*)::bigint AS length, max(day) AS last_day
+FROM streaks
+GROUP BY streak
+ORDER BY last_day DESC
+"#;
+
+#[derive(Default)]
+pub struct StatsQuery;
+
+#[Object]
+impl StatsQuery {
+    // everything here reads user_deal_rollup, which lags offer_audit by up to a refresh
+    async fn my_stats(
+        &self,
+        ctx: &Context<'_>,
+        range: Option<StatsRange>,
+        time_zone: Option<String>,
+    ) -> async_graphql::Result<MyStats> {
+        let db = ctx.data::<DatabaseConnection>()?;
+        let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+
+        let time_zone = time_zone.unwrap_or_else(|| DEFAULT_TIME_ZONE.to_owned());
+        let tz = time_zone
+            .parse::<Tz>()
+            .map_err(|e| anyhow::Error::msg(format!("invalid time zone: {e}")))?;
+
+        let now = chrono::offset::Utc::now().naive_utc();
+        let since = range.unwrap_or_default().since(now);
+
+        let conditions = Condition::all()
+            .add(user_deal_rollup::Column::UserId.eq(user_id))
+            .add(user_deal_rollup::Column::Hour.gte(since));
+
+        let totals = user_deal_rollup::Entity::find()
+            .select_only()
+            .column_as(Expr::cust("COALESCE(sum(deals), 0)::bigint"), "deals")
+            .column_as(
+                Expr::cust("COALESCE(sum(likely_used), 0)::bigint"),
+                "likely_used",
+            )
+            .column_as(
+                Expr::cust("COALESCE(sum(total_spent), 0)::double precision"),
+                "total_spent",
+            )
+            .column_as(
+                Expr::cust("COALESCE(sum(estimated_savings), 0)::double precision"),
+                "estimated_savings",
+            )
+            .filter(conditions.clone())
+            .into_model::<Totals>()
+            .one(db)
+            .await?
+            .context("aggregate must return a row")?;
+
+        let top_offers = user_deal_rollup::Entity::find()
+            .select_only()
+            .column(user_deal_rollup::Column::PropositionId)
+            .column_as(Expr::cust("sum(deals)::bigint"), "deals")
+            .filter(conditions.clone())
+            .group_by(user_deal_rollup::Column::PropositionId)
+            .order_by(Expr::cust("deals"), Order::Desc)
+            .limit(TOP_OFFERS)
+            .into_model::<OfferDeals>()
+            .all(db)
+            .await?;
+
+        let offer_details = offer_details::Entity::find()
+            .filter(
+                offer_details::Column::PropositionId
+                    .is_in(top_offers.iter().map(|o| o.proposition_id)),
+            )
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|d| (d.proposition_id, d))
+            .collect::<HashMap<_, _>>();
+
+        let favourite_store = user_deal_rollup::Entity::find()
+            .select_only()
+            .column(user_deal_rollup::Column::StoreId)
+            .column_as(Expr::cust("sum(deals)::bigint"), "deals")
+            .filter(conditions.clone())
+            .filter(user_deal_rollup::Column::StoreId.ne(""))
+            .group_by(user_deal_rollup::Column::StoreId)
+            .order_by(Expr::cust("deals"), Order::Desc)
+            .into_model::<StoreDeals>()
+            .one(db)
+            .await?;
+
+        let favourite_store = match favourite_store {
+            Some(store) => {
+                let name = stores::Entity::find_by_id(&store.store_id)
+                    .one(db)
+                    .await?
+                    .map(|s| s.name);
+
+                Some(StoreUsage {
+                    store_id: store.store_id,
+                    name,
+                    deals: store.deals,
+                })
+            }
+            None => None,
+        };
+
+        let by_weekday = deals_by(db, conditions.clone(), "isodow", &time_zone).await?;
+        let by_hour = deals_by(db, conditions, "hour", &time_zone).await?;
+
+        let streaks = Streak::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            STREAKS_SQL,
+            [user_id.into(), time_zone.into(), since.into()],
+        ))
+        .all(db)
+        .await?;
+
+        let yesterday = chrono::offset::Utc::now()
+            .with_timezone(&tz)
+            .date_naive()
+            .checked_sub_days(Days::new(1))
+            .context("date out of range")?;
+
+        Ok(MyStats {
+            total_deals: totals.deals,
+            likely_used: totals.likely_used,
+            total_spent: totals.total_spent,
+            estimated_savings: totals.estimated_savings,
+            top_offers: top_offers
+                .into_iter()
+                .map(|o| {
+                    let details = offer_details.get(&o.proposition_id);
+                    OfferUsage {
+                        offer_proposition_id: o.proposition_id,
+                        name: details.map(|d| d.name.clone()),
+                        short_name: details.map(|d| d.short_name.clone()),
+                        deals: o.deals,
+                    }
+                })
+                .collect(),
+            favourite_store,
+            by_weekday,
+            by_hour,
+            current_streak: streaks
+                .first()
+                .filter(|s| s.last_day >= yesterday)
+                .map(|s| s.length)
+                .unwrap_or_default(),
+            longest_streak: streaks.iter().map(|s| s.length).max().unwrap_or_default(),
+        })
+    }
+}
+
+// field is anything extract() takes, counted in the user's time zone
+async fn deals_by(
+    db: &DatabaseConnection,
+    conditions: Condition,
+    field: &'static str,
+    time_zone: &str,
+) -> Result<Vec<DealBucket>, sea_orm::DbErr> {
+    user_deal_rollup::Entity::find()
+        .select_only()
+        .column_as(
+            Expr::cust_with_values(
+                format!("extract({field} FROM hour AT TIME ZONE 'UTC' AT TIME ZONE $1)::int"),
+                [time_zone],
+            ),
+            "bucket",
+        )
+        .column_as(Expr::cust("sum(deals)::bigint"), "deals")
+        .filter(conditions)
+        .group_by(Expr::cust("bucket"))
+        .order_by(Expr::cust("bucket"), Order::Asc)
+        .into_model::<DealBucket>()
+        .all(db)
+        .await
+}
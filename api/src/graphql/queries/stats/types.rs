@@ -0,0 +1,92 @@
+use async_graphql::{Enum, SimpleObject};
+use chrono::{Days, Months};
+use sea_orm::{FromQueryResult, prelude::DateTime};
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Default)]
+pub enum StatsRange {
+    Week,
+    Month,
+    Year,
+    #[default]
+    AllTime,
+}
+
+impl StatsRange {
+    pub fn since(&self, now: DateTime) -> DateTime {
+        let since = match self {
+            StatsRange::Week => now.checked_sub_days(Days::new(7)),
+            StatsRange::Month => now.checked_sub_months(Months::new(1)),
+            StatsRange::Year => now.checked_sub_months(Months::new(12)),
+            StatsRange::AllTime => None,
+        };
+
+        // epoch rather than DateTime::MIN, which postgres can't represent
+        since.unwrap_or_default()
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct MyStats {
+    pub total_deals: i64,
+    pub likely_used: i64,
+    // sum of offer_details.price, what the deals cost rather than what they saved
+    pub total_spent: f64,
+    // menu price at the deal's store minus the deal price, deals that can't be priced
+    // that way count for nothing
+    pub estimated_savings: f64,
+    pub top_offers: Vec<OfferUsage>,
+    pub favourite_store: Option<StoreUsage>,
+    // isodow, monday is 1
+    pub by_weekday: Vec<DealBucket>,
+    pub by_hour: Vec<DealBucket>,
+    // consecutive days with a deal, current only counts if it reaches today or yesterday
+    pub current_streak: i64,
+    pub longest_streak: i64,
+}
+
+#[derive(SimpleObject)]
+pub struct OfferUsage {
+    pub offer_proposition_id: i64,
+    pub name: Option<String>,
+    pub short_name: Option<String>,
+    pub deals: i64,
+}
+
+#[derive(SimpleObject)]
+pub struct StoreUsage {
+    pub store_id: String,
+    pub name: Option<String>,
+    pub deals: i64,
+}
+
+#[derive(SimpleObject, FromQueryResult)]
+pub struct DealBucket {
+    pub bucket: i32,
+    pub deals: i64,
+}
+
+#[derive(FromQueryResult)]
+pub struct Totals {
+    pub deals: i64,
+    pub likely_used: i64,
+    pub total_spent: f64,
+    pub estimated_savings: f64,
+}
+
+#[derive(FromQueryResult)]
+pub struct OfferDeals {
+    pub proposition_id: i64,
+    pub deals: i64,
+}
+
+#[derive(FromQueryResult)]
+pub struct StoreDeals {
+    pub store_id: String,
+    pub deals: i64,
+}
+
+#[derive(FromQueryResult)]
+pub struct Streak {
+    pub length: i64,
+    pub last_day: chrono::NaiveDate,
+}
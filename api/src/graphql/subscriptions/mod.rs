@@ -0,0 +1,97 @@
+use crate::{
+    graphql::ValidatedClaims,
+    offer_events::{
+        DealStackChange, OfferChange, OfferEvent, OfferEventBroker, WaitlistNotification,
+    },
+};
+use anyhow::Context as _;
+use async_graphql::{Context, MergedSubscription, Subscription};
+use base::jwt::Role;
+use entity::{offer_audit, sea_orm_active_enums::Action};
+use futures::{Stream, StreamExt};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, prelude::Uuid};
+
+#[derive(Default, MergedSubscription)]
+pub struct SubscriptionRoot(OffersSubscription);
+
+#[derive(Default)]
+pub struct OffersSubscription;
+
+#[Subscription]
+impl OffersSubscription {
+    async fn offer_added(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<impl Stream<Item = OfferChange>> {
+        let broker = ctx.data::<OfferEventBroker>()?;
+
+        Ok(broker.subscribe().filter_map(|event| async move {
+            match event {
+                OfferEvent::OfferAdded(change) => Some(change),
+                _ => None,
+            }
+        }))
+    }
+
+    // the offer was taken (account locked) or it expired
+    async fn offer_removed(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<impl Stream<Item = OfferChange>> {
+        let broker = ctx.data::<OfferEventBroker>()?;
+
+        Ok(broker.subscribe().filter_map(|event| async move {
+            match event {
+                OfferEvent::OfferRemoved(change) => Some(change),
+                _ => None,
+            }
+        }))
+    }
+
+    async fn deal_stack_changed(
+        &self,
+        ctx: &Context<'_>,
+        transaction_id: Uuid,
+    ) -> async_graphql::Result<impl Stream<Item = DealStackChange>> {
+        let broker = ctx.data::<OfferEventBroker>()?;
+        let db = ctx.data::<DatabaseConnection>()?;
+        let claims = &ctx.data::<ValidatedClaims>()?.0;
+        let user_id = claims.user_id.parse::<Uuid>()?;
+
+        // same ownership rule as find_active_deal
+        offer_audit::Entity::find()
+            .filter(offer_audit::Column::TransactionId.eq(transaction_id))
+            .filter(offer_audit::Column::Action.eq(Action::Add))
+            .one(db)
+            .await?
+            .filter(|a| a.user_id == Some(user_id) || claims.role.contains(&Role::Admin))
+            .context("No deal found for this transaction")?;
+
+        Ok(broker.subscribe().filter_map(move |event| async move {
+            match event {
+                OfferEvent::DealStackChanged(change) if change.transaction_id == transaction_id => {
+                    Some(change)
+                }
+                _ => None,
+            }
+        }))
+    }
+
+    // the caller reached the front of the waitlist and an account was just released
+    async fn waitlist_offer_available(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<impl Stream<Item = WaitlistNotification>> {
+        let broker = ctx.data::<OfferEventBroker>()?;
+        let user_id = ctx.data::<ValidatedClaims>()?.0.user_id.parse::<Uuid>()?;
+
+        Ok(broker.subscribe().filter_map(move |event| async move {
+            match event {
+                OfferEvent::WaitlistNotified(notification) if notification.user_id == user_id => {
+                    Some(notification)
+                }
+                _ => None,
+            }
+        }))
+    }
+}
@@ -16,10 +16,15 @@ pub mod create_account;
 pub mod error;
 pub mod generate_recommendations;
 pub mod job_executor;
+pub mod prune_waitlist;
 pub mod recategorise_offers;
+pub mod reconcile_active_deals;
 pub mod refresh;
+pub mod refresh_menu_catalog;
+pub mod refresh_user_stats;
 pub mod save_images;
 pub mod shared;
+pub mod sync_stores;
 
 #[async_trait::async_trait]
 pub trait Job: Send + Sync + Debug {
@@ -0,0 +1,24 @@
+use super::{Job, JobContext, error::JobError};
+use crate::reservations::prune_waitlist;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug)]
+pub struct PruneWaitlistJob;
+
+#[async_trait::async_trait]
+impl Job for PruneWaitlistJob {
+    fn name(&self) -> String {
+        "prune_waitlist".to_owned()
+    }
+
+    async fn execute(
+        &self,
+        context: &JobContext,
+        _cancellation_token: CancellationToken,
+    ) -> Result<(), JobError> {
+        let pruned = prune_waitlist(context.database).await?;
+        tracing::info!("pruned {pruned} waitlist entries");
+
+        Ok(())
+    }
+}
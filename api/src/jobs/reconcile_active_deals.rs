@@ -0,0 +1,82 @@
+use super::{Job, JobContext, error::JobError};
+use sea_orm::{DbBackend, FromQueryResult, Statement, prelude::Uuid};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+// one statement so nothing lands between reading the view and writing the counts. users
+// with a count but nothing in the view are set back to 0
+const RECONCILE_SQL: &str = r#"
+WITH actual AS (
+    SELECT user_id, count(*)::int AS actual
+    FROM active_transactions
+    GROUP BY user_id
+),
+discrepancies AS (
+    SELECT
+        COALESCE(c.user_id, a.user_id) AS user_id,
+        COALESCE(c.count, 0) AS recorded,
+        COALESCE(a.actual, 0) AS actual
+    FROM concurrent_active_deals c
+    FULL JOIN actual a ON a.user_id = c.user_id
+    WHERE COALESCE(c.count, 0) <> COALESCE(a.actual, 0)
+),
+reconciled AS (
+    INSERT INTO concurrent_active_deals (user_id, count)
+    SELECT user_id, actual FROM discrepancies
+    ON CONFLICT (user_id) DO UPDATE SET count = EXCLUDED.count
+)
+SELECT user_id, recorded, actual FROM discrepancies
+"#;
+
+#[derive(Debug)]
+pub struct ReconcileActiveDealsJob;
+
+#[derive(Serialize, Deserialize, Debug, FromQueryResult)]
+struct Discrepancy {
+    user_id: Uuid,
+    recorded: i32,
+    actual: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ReconcileActiveDealsContext {
+    discrepancies: Vec<Discrepancy>,
+}
+
+#[async_trait::async_trait]
+impl Job for ReconcileActiveDealsJob {
+    fn name(&self) -> String {
+        "reconcile_active_deals".to_owned()
+    }
+
+    // the counter drifts when a cleanup fails or is marked duplicate, so it is recomputed
+    // from the active_transactions view
+    async fn execute(
+        &self,
+        context: &JobContext,
+        _cancellation_token: CancellationToken,
+    ) -> Result<(), JobError> {
+        let discrepancies = Discrepancy::find_by_statement(Statement::from_string(
+            DbBackend::Postgres,
+            RECONCILE_SQL,
+        ))
+        .all(context.database)
+        .await?;
+
+        for discrepancy in &discrepancies {
+            tracing::warn!(
+                "active deals for {} recorded as {} but {} are active",
+                discrepancy.user_id,
+                discrepancy.recorded,
+                discrepancy.actual
+            );
+        }
+
+        tracing::info!("reconciled {} active deal counts", discrepancies.len());
+        context
+            .set(ReconcileActiveDealsContext { discrepancies })
+            .await?;
+
+        Ok(())
+    }
+}
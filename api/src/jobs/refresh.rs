@@ -11,16 +11,21 @@ use sea_orm::{
     sea_query::{LockBehavior, LockType},
 };
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 use tokio_util::sync::CancellationToken;
 
 #[derive(Debug)]
 pub struct RefreshJob {
     pub http_client: ClientWithMiddleware,
     pub mcdonalds_config: crate::settings::McDonalds,
+    // runs are skipped until then after the api rate limits us
+    pub rate_limited_until: Mutex<Option<Instant>>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 struct RefreshContext {
     events_to_dispatch: Vec<Event>,
 }
@@ -38,6 +43,16 @@ impl Job for RefreshJob {
         context: &JobContext,
         cancellation_token: CancellationToken,
     ) -> Result<(), JobError> {
+        let rate_limited_until = *self
+            .rate_limited_until
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if rate_limited_until.is_some_and(|until| until > Instant::now()) {
+            tracing::info!("rate limited, skipping refresh");
+            context.set(RefreshContext::default()).await?;
+            return Ok(());
+        }
+
         let account_to_refresh = accounts::Entity::find()
             .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
             .filter(accounts::Column::Active.eq(true))
@@ -57,7 +72,16 @@ impl Job for RefreshJob {
             caching,
             cancellation_token,
         )
-        .await?;
+        .await
+        .inspect_err(|e| {
+            if let Some(backoff) = shared::rate_limited_for(e) {
+                tracing::warn!("rate limited, backing off for {backoff:?}");
+                *self
+                    .rate_limited_until
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner()) = Some(Instant::now() + backoff);
+            }
+        })?;
 
         context.set(RefreshContext { events_to_dispatch }).await?;
 
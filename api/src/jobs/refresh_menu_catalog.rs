@@ -0,0 +1,143 @@
+use super::{Job, JobContext, error::JobError};
+use anyhow::Context as _;
+use base::constants::{
+    MACCAS_ACCOUNT_REFRESH_FAILURE,
+    mc_donalds::{COUNTRY_CODE, FILTER},
+};
+use converters::Database;
+use entity::{accounts, product_prices, products};
+use reqwest_middleware::ClientWithMiddleware;
+use sea_orm::{
+    ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, sea_query::OnConflict,
+};
+use std::collections::HashSet;
+use tokio_util::sync::CancellationToken;
+
+// keep each insert well under the postgres bind parameter limit
+const INSERT_CHUNK_SIZE: usize = 500;
+
+#[derive(Debug)]
+pub struct RefreshMenuCatalogJob {
+    pub http_client: ClientWithMiddleware,
+    pub mcdonalds_config: crate::settings::McDonalds,
+    pub store_ids: Vec<String>,
+}
+
+#[async_trait::async_trait]
+impl Job for RefreshMenuCatalogJob {
+    fn name(&self) -> String {
+        "refresh_menu_catalog".to_owned()
+    }
+
+    async fn execute(
+        &self,
+        context: &JobContext,
+        cancellation_token: CancellationToken,
+    ) -> Result<(), JobError> {
+        if self.store_ids.is_empty() {
+            tracing::warn!("no reference stores configured, skipping menu catalog refresh");
+            return Ok(());
+        }
+
+        let account = accounts::Entity::find()
+            .filter(accounts::Column::Active.eq(true))
+            .filter(accounts::Column::RefreshFailureCount.lte(MACCAS_ACCOUNT_REFRESH_FAILURE))
+            .order_by_desc(accounts::Column::UpdatedAt)
+            .one(context.database_connection)
+            .await?
+            .context("no account found")?;
+
+        let api_client = base::maccas::get_maccas_api_client(
+            &account,
+            self.http_client.clone(),
+            &self.mcdonalds_config.client_id,
+            context.database_connection,
+        );
+
+        for store_id in &self.store_ids {
+            if cancellation_token.is_cancelled() {
+                break;
+            }
+
+            let catalog = api_client
+                .get_menu_catalog(COUNTRY_CODE, store_id, FILTER)
+                .await?
+                .body;
+
+            for store in catalog.store {
+                let products = store
+                    .products
+                    .iter()
+                    .flat_map(Database::<products::Model>::convert_product)
+                    .map(|p| p.0)
+                    .collect::<Vec<_>>();
+
+                // prices can reference products that aren't in this catalog
+                let product_codes = products
+                    .iter()
+                    .map(|p| p.product_code)
+                    .collect::<HashSet<_>>();
+
+                let prices = store
+                    .product_price
+                    .iter()
+                    .filter(|p| product_codes.contains(&p.product_code))
+                    .flat_map(|p| {
+                        Database::<product_prices::Model>::convert_product_prices(store_id, p)
+                    })
+                    .map(|p| p.0)
+                    .collect::<Vec<_>>();
+
+                tracing::info!(
+                    "store {store_id}: {} products, {} prices",
+                    products.len(),
+                    prices.len()
+                );
+
+                for chunk in products.chunks(INSERT_CHUNK_SIZE) {
+                    products::Entity::insert_many(
+                        chunk.iter().cloned().map(|m| m.into_active_model()),
+                    )
+                    .on_conflict(
+                        OnConflict::column(products::Column::ProductCode)
+                            .update_columns([
+                                products::Column::Name,
+                                products::Column::ShortName,
+                                products::Column::ImageName,
+                                products::Column::Kilojoules,
+                                products::Column::Nutrition,
+                                products::Column::CategoryIds,
+                                products::Column::MenuTypeId,
+                                products::Column::IsPromotional,
+                                products::Column::IsSalable,
+                            ])
+                            .to_owned(),
+                    )
+                    .on_empty_do_nothing()
+                    .exec(context.database)
+                    .await?;
+                }
+
+                for chunk in prices.chunks(INSERT_CHUNK_SIZE) {
+                    product_prices::Entity::insert_many(
+                        chunk.iter().cloned().map(|m| m.into_active_model()),
+                    )
+                    .on_conflict(
+                        OnConflict::columns([
+                            product_prices::Column::StoreId,
+                            product_prices::Column::ProductCode,
+                            product_prices::Column::PriceTypeId,
+                        ])
+                        .update_column(product_prices::Column::Price)
+                        .to_owned(),
+                    )
+                    .on_empty_do_nothing()
+                    .exec(context.database)
+                    .await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+use super::{Job, JobContext, error::JobError};
+use sea_orm::ConnectionTrait;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug)]
+pub struct RefreshUserStatsJob;
+
+#[async_trait::async_trait]
+impl Job for RefreshUserStatsJob {
+    fn name(&self) -> String {
+        "refresh_user_stats".to_owned()
+    }
+
+    async fn execute(
+        &self,
+        context: &JobContext,
+        _cancellation_token: CancellationToken,
+    ) -> Result<(), JobError> {
+        // concurrently so myStats keeps reading the old rollup while this runs
+        context
+            .database
+            .execute_unprepared("REFRESH MATERIALIZED VIEW CONCURRENTLY user_deal_rollup")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -1,17 +1,28 @@
 use super::error::JobError;
-use crate::caching::{OfferDetailsCache, ProtobufTimestamp, protos};
+use crate::{
+    caching::{OfferDetailsCache, ProtobufTimestamp, protos},
+    offer_events::{self, OfferEvent, OfferRemovedReason},
+};
 use api::Event;
 use base::constants::mc_donalds;
 use converters::Database;
-use entity::{account_lock, accounts, offer_details, offer_history, offers};
-use libmaccas::ApiClient;
+use entity::{account_lock, accounts, offer_details, offer_history, offers, stores};
+use libmaccas::ClientError;
 use reqwest_middleware::ClientWithMiddleware;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, DbErr, EntityTrait,
-    IntoActiveModel, QueryFilter, Set, TransactionTrait, TryIntoModel, sea_query::OnConflict,
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DatabaseTransaction, DbErr, EntityTrait,
+    IntoActiveModel, QueryFilter, TransactionTrait, TryIntoModel,
+    sea_query::{Expr, OnConflict},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
 };
 use tokio_util::sync::CancellationToken;
 
+// for a 429 without a Retry-After
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
 pub fn offer_details_model_to_cache(
     details: &entity::offer_details::Model,
 ) -> protos::OfferDetails {
@@ -46,6 +57,47 @@ pub fn offer_details_model_to_cache(
     }
 }
 
+// summaries from a location search, keeps the time zone and ordering details that only
+// get_restaurant knows about
+pub async fn upsert_store_summaries<C>(db: &C, models: Vec<stores::Model>) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    // postgres rejects an upsert that touches the same row twice
+    let models = models
+        .into_iter()
+        .map(|m| (m.id.clone(), m))
+        .collect::<HashMap<_, _>>();
+
+    stores::Entity::insert_many(models.into_values().map(|m| m.into_active_model()))
+        .on_conflict(
+            OnConflict::column(stores::Column::Id)
+                .update_columns([
+                    stores::Column::Name,
+                    stores::Column::Address,
+                    stores::Column::Latitude,
+                    stores::Column::Longitude,
+                    stores::Column::OpeningHours,
+                    stores::Column::Facilities,
+                    stores::Column::DriveThru,
+                    stores::Column::McCafe,
+                    stores::Column::Open24h,
+                    stores::Column::Suburb,
+                    stores::Column::Postcode,
+                ])
+                .value(
+                    stores::Column::TimeZone,
+                    Expr::cust("COALESCE(excluded.time_zone, stores.time_zone)"),
+                )
+                .to_owned(),
+        )
+        .on_empty_do_nothing()
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
 pub async fn refresh_account(
     account_to_refresh: entity::accounts::Model,
     http_client: &ClientWithMiddleware,
@@ -56,58 +108,21 @@ pub async fn refresh_account(
     _cancellation_token: CancellationToken,
 ) -> Result<Vec<Event>, JobError> {
     let account_id = account_to_refresh.id.to_owned();
-    let current_failure_count = account_to_refresh.refresh_failure_count;
-    let api_client = async {
-        tracing::info!("refreshing account: {:?}", &account_id);
-
-        let mut api_client = libmaccas::ApiClient::new(
-            base::constants::mc_donalds::BASE_URL.to_owned(),
-            http_client.clone(),
-            mcdonalds_config.client_id.clone(),
-        );
-
-        api_client.set_auth_token(&account_to_refresh.access_token);
-        let response = api_client
-            .customer_login_refresh(&account_to_refresh.refresh_token)
-            .await?;
-
-        let response = response
-            .body
-            .response
-            .ok_or_else(|| anyhow::Error::msg("access token refresh failed"))?;
-
-        api_client.set_auth_token(&response.access_token);
-
-        let mut update_model = account_to_refresh.into_active_model();
-
-        update_model.access_token = Set(response.access_token);
-        update_model.refresh_token = Set(response.refresh_token);
-        tracing::info!("new tokens fetched, updating database");
-
-        // MUST USE CONNECTION OR WE'LL LOSE CREDENTIALS AND 401
-        update_model.update(db_connection).await?;
-
-        Ok::<ApiClient, anyhow::Error>(api_client)
+    tracing::info!("refreshing account: {:?}", &account_id);
+
+    let api_client = base::maccas::get_maccas_api_client(
+        &account_to_refresh,
+        http_client.clone(),
+        &mcdonalds_config.client_id,
+        db_connection,
+    );
+
+    if let Err(e) = api_client.refresh_tokens().await {
+        record_client_error(db_connection, &account_to_refresh, &e).await?;
+        return Err(e.into());
     }
-    .await;
-
-    let api_client = match api_client {
-        Ok(api_client) => api_client,
-        Err(e) => {
-            tracing::warn!("increasing error count: {}", current_failure_count + 1);
-            accounts::Entity::update(accounts::ActiveModel {
-                id: sea_orm::Unchanged(account_id),
-                refresh_failure_count: sea_orm::Set(current_failure_count + 1),
-                ..Default::default()
-            })
-            .exec(db_connection)
-            .await?;
-
-            return Err(e.into());
-        }
-    };
 
-    let offers = api_client
+    let offers = match api_client
         .get_offers(
             mc_donalds::DISTANCE,
             mc_donalds::LATITUDE,
@@ -115,7 +130,14 @@ pub async fn refresh_account(
             "",
             mc_donalds::OFFSET,
         )
-        .await?;
+        .await
+    {
+        Ok(offers) => offers,
+        Err(e) => {
+            record_client_error(db_connection, &account_to_refresh, &e).await?;
+            return Err(e.into());
+        }
+    };
 
     let offer_list = offers.body.response.unwrap_or_default();
     tracing::info!("{} offers found", offer_list.offers.len());
@@ -196,6 +218,16 @@ pub async fn refresh_account(
         .map(|d| d.0.into_active_model())
         .collect::<Vec<_>>();
 
+    let previous_offers = offers::Entity::find()
+        .filter(offers::Column::AccountId.eq(account_id))
+        .all(&txn)
+        .await?;
+    let current_offers = models
+        .iter()
+        .cloned()
+        .flat_map(|m| m.try_into_model())
+        .collect::<Vec<_>>();
+
     offers::Entity::delete_many()
         .filter(offers::Column::AccountId.eq(account_id))
         .exec(&txn)
@@ -235,9 +267,97 @@ pub async fn refresh_account(
     txn.commit().await?;
 
     // unlock account now if it was locked...
-    let _ = account_lock::Entity::delete_by_id(account_id)
+    let was_locked = account_lock::Entity::delete_by_id(account_id)
         .exec(db)
-        .await;
+        .await
+        .is_ok_and(|r| r.rows_affected > 0);
+
+    if let Err(e) = publish_offer_changes(db, &previous_offers, &current_offers, was_locked).await {
+        tracing::warn!("error publishing offer changes: {e}");
+    }
 
     Ok(events_to_dispatch)
 }
+
+// only errors that are about the account itself count against it, token store failures
+// come back as ClientError::Other and rate limits are left to the caller
+async fn record_client_error(
+    db: &DatabaseConnection,
+    account: &accounts::Model,
+    error: &ClientError,
+) -> Result<(), DbErr> {
+    match error {
+        ClientError::Unauthorized => {
+            let failure_count = account.refresh_failure_count + 1;
+            tracing::warn!("increasing error count: {failure_count}");
+            accounts::Entity::update(accounts::ActiveModel {
+                id: sea_orm::Unchanged(account.id),
+                refresh_failure_count: sea_orm::Set(failure_count),
+                ..Default::default()
+            })
+            .exec(db)
+            .await?;
+        }
+        // locked upstream, nothing we send will work until someone unlocks it by hand
+        ClientError::AccountLocked => {
+            tracing::warn!("{} is locked upstream, disabling it", account.id);
+            accounts::Entity::update(accounts::ActiveModel {
+                id: sea_orm::Unchanged(account.id),
+                active: sea_orm::Set(false),
+                ..Default::default()
+            })
+            .exec(db)
+            .await?;
+        }
+        e => tracing::warn!("not counting refresh failure for {}: {e}", account.id),
+    }
+
+    Ok(())
+}
+
+// how long to leave the api alone when it rate limited us, None for any other error
+pub fn rate_limited_for(error: &JobError) -> Option<Duration> {
+    match error {
+        JobError::McDonaldsClientError(ClientError::RateLimited { retry_after }) => {
+            Some(retry_after.unwrap_or(RATE_LIMIT_BACKOFF))
+        }
+        _ => None,
+    }
+}
+
+// a locked account's offers were already announced as removed, so all of them come back
+async fn publish_offer_changes<C>(
+    db: &C,
+    previous_offers: &[offers::Model],
+    current_offers: &[offers::Model],
+    was_locked: bool,
+) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    let previous_ids = previous_offers
+        .iter()
+        .map(|o| o.offer_proposition_id)
+        .collect::<HashSet<_>>();
+    let current_ids = current_offers
+        .iter()
+        .map(|o| o.offer_proposition_id)
+        .collect::<HashSet<_>>();
+
+    for offer in current_offers {
+        if was_locked || !previous_ids.contains(&offer.offer_proposition_id) {
+            offer_events::publish(db, &OfferEvent::added(offer)).await?;
+        }
+    }
+
+    if !was_locked {
+        for offer in previous_offers {
+            if !current_ids.contains(&offer.offer_proposition_id) {
+                let event = OfferEvent::removed(offer, OfferRemovedReason::Expired);
+                offer_events::publish(db, &event).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
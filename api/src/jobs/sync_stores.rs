@@ -0,0 +1,167 @@
+use super::{Job, JobContext, error::JobError, shared::upsert_store_summaries};
+use anyhow::Context as _;
+use base::constants::{MACCAS_ACCOUNT_REFRESH_FAILURE, mc_donalds::FILTER};
+use chrono::TimeDelta;
+use converters::Database;
+use entity::{accounts, store_search_areas, stores};
+use reqwest_middleware::ClientWithMiddleware;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use std::collections::VecDeque;
+use tokio_util::sync::CancellationToken;
+
+// capital cities and the larger regional centres, the crawl spreads out from here
+const SEED_LOCATIONS: &[(f64, f64)] = &[
+    (-33.8688, 151.2093),
+    (-37.8136, 144.9631),
+    (-27.4698, 153.0251),
+    (-31.9523, 115.8613),
+    (-34.9285, 138.6007),
+    (-42.8821, 147.3272),
+    (-35.2809, 149.1300),
+    (-12.4634, 130.8456),
+    (-28.0167, 153.4000),
+    (-32.9283, 151.7817),
+    (-34.4278, 150.8931),
+    (-19.2590, 146.8169),
+    (-16.9186, 145.7781),
+    (-23.6980, 133.8807),
+    (-38.1499, 144.3617),
+    (-37.5622, 143.8503),
+    (-36.7570, 144.2794),
+    (-41.4332, 147.1441),
+    (-23.3791, 150.5100),
+    (-21.1411, 149.1861),
+    (-24.8661, 152.3489),
+    (-27.5598, 151.9507),
+    (-30.2962, 153.1135),
+    (-35.1082, 147.3598),
+    (-36.0737, 146.9135),
+    (-33.2839, 149.1010),
+    (-32.2569, 148.6011),
+    (-28.7774, 114.6150),
+    (-33.3271, 115.6414),
+    (-20.7256, 139.4927),
+];
+// upstream search distance, same units as LOCATION_SEARCH_DISTANCE
+const SEARCH_DISTANCE: u64 = 50_000;
+// skip searching around a store that's this close to somewhere already searched
+const SEARCH_SPACING_METERS: f64 = 15_000.0;
+const MAX_SEARCHES: usize = 2_000;
+// the job runs weekly, an area it hasn't searched again since is no longer trusted to
+// have every store in it
+pub const SEARCH_AREA_LIFETIME: TimeDelta = TimeDelta::days(8);
+
+#[derive(Debug)]
+pub struct SyncStoresJob {
+    pub http_client: ClientWithMiddleware,
+    pub mcdonalds_config: crate::settings::McDonalds,
+}
+
+#[async_trait::async_trait]
+impl Job for SyncStoresJob {
+    fn name(&self) -> String {
+        "sync_stores".to_owned()
+    }
+
+    async fn execute(
+        &self,
+        context: &JobContext,
+        cancellation_token: CancellationToken,
+    ) -> Result<(), JobError> {
+        let account = accounts::Entity::find()
+            .filter(accounts::Column::Active.eq(true))
+            .filter(accounts::Column::RefreshFailureCount.lte(MACCAS_ACCOUNT_REFRESH_FAILURE))
+            .order_by_desc(accounts::Column::UpdatedAt)
+            .one(context.database_connection)
+            .await?
+            .context("no account found")?;
+
+        let api_client = base::maccas::get_maccas_api_client(
+            &account,
+            self.http_client.clone(),
+            &self.mcdonalds_config.client_id,
+            context.database_connection,
+        );
+
+        // search results only come back for stores near the point, so every store we find
+        // becomes another point to search from until nothing new turns up
+        let mut queue = SEED_LOCATIONS.iter().copied().collect::<VecDeque<_>>();
+        let mut searched: Vec<geoutils::Location> = Vec::new();
+        let mut stores_found = 0;
+
+        while let Some((latitude, longitude)) = queue.pop_front() {
+            if cancellation_token.is_cancelled() {
+                break;
+            }
+
+            if searched.len() >= MAX_SEARCHES {
+                tracing::warn!("hit search limit with {} points left", queue.len());
+                break;
+            }
+
+            let point = geoutils::Location::new(latitude, longitude);
+            let already_searched = searched
+                .iter()
+                .any(|s| s.haversine_distance_to(&point).meters() < SEARCH_SPACING_METERS);
+            if already_searched {
+                continue;
+            }
+            searched.push(point);
+
+            let response = api_client
+                .restaurant_location(&SEARCH_DISTANCE, &latitude, &longitude, FILTER)
+                .await;
+
+            let restaurants = match response {
+                Ok(r) => r.body.response.map(|r| r.restaurants),
+                Err(e) if e.is_transient() => {
+                    tracing::warn!("error searching around {latitude},{longitude}: {e}");
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let Some(restaurants) = restaurants else {
+                tracing::warn!("no response searching around {latitude},{longitude}");
+                continue;
+            };
+
+            queue.extend(
+                restaurants
+                    .iter()
+                    .map(|r| (r.location.latitude, r.location.longitude)),
+            );
+
+            let models = restaurants
+                .iter()
+                .flat_map(Database::<stores::Model>::convert_restaurant_summary)
+                .map(|m| m.0)
+                .collect::<Vec<_>>();
+
+            stores_found += models.len();
+            upsert_store_summaries(context.database, models).await?;
+
+            store_search_areas::ActiveModel {
+                latitude: Set(latitude),
+                longitude: Set(longitude),
+                radius_meters: Set(SEARCH_DISTANCE as f64),
+                ..Default::default()
+            }
+            .insert(context.database)
+            .await?;
+        }
+
+        let expired_before = chrono::offset::Utc::now().naive_utc() - SEARCH_AREA_LIFETIME;
+        store_search_areas::Entity::delete_many()
+            .filter(store_search_areas::Column::SearchedAt.lt(expired_before))
+            .exec(context.database)
+            .await?;
+
+        tracing::info!(
+            "searched {} points, {stores_found} store results",
+            searched.len()
+        );
+
+        Ok(())
+    }
+}
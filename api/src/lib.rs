@@ -7,4 +7,5 @@ pub use events::CreateEventResponse;
 pub use events::Event;
 pub use events::ExistingEvent;
 pub use events::GetEventsHistoryResponse;
+pub use events::GetNotificationDeliveriesResponse;
 pub use events::Health;
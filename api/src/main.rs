@@ -2,25 +2,34 @@ use crate::jobs::{
     account_unlock::AccountUnlockJob, activate_account::ActivateAccountJob,
     activate_existing_account::ActivateExistingAccount, categorise_offers::CategoriseOffersJob,
     create_account::CreateAccountJob, generate_recommendations::GenerateRecommendationsJob,
-    job_executor::JobExecutor, recategorise_offers::RecategoriseOffersJob, refresh::RefreshJob,
-    save_images::SaveImagesJob,
+    job_executor::JobExecutor, prune_waitlist::PruneWaitlistJob,
+    recategorise_offers::RecategoriseOffersJob, reconcile_active_deals::ReconcileActiveDealsJob,
+    refresh::RefreshJob, refresh_menu_catalog::RefreshMenuCatalogJob,
+    refresh_user_stats::RefreshUserStatsJob, save_images::SaveImagesJob,
+    sync_stores::SyncStoresJob,
 };
 use crate::{
     event_manager::EventManager,
     routes::{
-        create_event::create_bulk_events, create_event::create_event,
+        create_event::create_bulk_events,
+        create_event::create_event,
         get_events::get_events_history,
+        notification_deliveries::{get_notification_deliveries, replay_notification_delivery},
     },
 };
 use crate::{
     graphql::{
-        FinalSchema, MutationRoot, QueryRoot, graphql_handler,
-        queries::locations::dataloader::LocationLoader, self_health,
+        FinalSchema, MutationRoot, QueryRoot, graphql_handler, graphql_ws_handler,
+        queries::locations::{dataloader::LocationLoader, geocoder::get_geocoder},
+        queries::products::dataloader::{ProductLoader, ProductPriceLoader},
+        self_health,
+        subscriptions::SubscriptionRoot,
     },
+    offer_events::OfferEventBroker,
     settings::Settings,
     types::ApiState,
 };
-use async_graphql::{EmptySubscription, dataloader::DataLoader};
+use async_graphql::dataloader::DataLoader;
 use axum::routing::post;
 use axum::{Router, http::Method, routing::get};
 use axum_tracing_opentelemetry::middleware::{OtelAxumLayer, OtelInResponseLayer};
@@ -30,7 +39,10 @@ use event_manager::S3BucketType;
 use graphql::health;
 use graphql::{
     graphiql,
-    queries::offers::dataloader::{OfferCountDataLoader, OfferDetailsLoader},
+    queries::offers::dataloader::{
+        DealStackCodeLoader, OfferCountDataLoader, OfferDetailsLoader, OfferHistoryLoader,
+        OfferProductSetsLoader,
+    },
 };
 use jobs::job_executor;
 use reqwest_middleware::ClientWithMiddleware;
@@ -42,12 +54,18 @@ use tower_http::cors::CorsLayer;
 use tracing::log::LevelFilter;
 
 mod caching;
+mod deal_limits;
+mod discord_bot;
 mod discord_webhook;
 mod event_manager;
 mod graphql;
 mod jobs;
 mod macros;
+mod notification_channels;
+mod offer_events;
+mod push_notifications;
 mod queue;
+mod reservations;
 mod result_extension;
 mod routes;
 mod settings;
@@ -73,6 +91,7 @@ async fn init_job_executor(
         .add(RefreshJob {
             http_client: http_client.clone(),
             mcdonalds_config: settings.mcdonalds.clone(),
+            rate_limited_until: Default::default(),
         })
         .await;
 
@@ -125,6 +144,25 @@ async fn init_job_executor(
         })
         .await;
 
+    scheduler
+        .add(RefreshMenuCatalogJob {
+            http_client: http_client.clone(),
+            mcdonalds_config: settings.mcdonalds.clone(),
+            store_ids: settings.menu_catalog.store_ids.clone(),
+        })
+        .await;
+
+    scheduler.add(RefreshUserStatsJob).await;
+    scheduler.add(ReconcileActiveDealsJob).await;
+    scheduler.add(PruneWaitlistJob).await;
+
+    scheduler
+        .add(SyncStoresJob {
+            http_client: http_client.clone(),
+            mcdonalds_config: settings.mcdonalds.clone(),
+        })
+        .await;
+
     scheduler.init().await?;
 
     Ok(scheduler)
@@ -200,11 +238,28 @@ async fn main() -> Result<(), anyhow::Error> {
 
     let http_client = base::http::get_http_client()?;
     let basic_http_client = base::http::get_basic_http_client()?;
+    let geocoder = get_geocoder(&settings, http_client.clone(), db.clone());
+    let openai_api_client =
+        openai::ApiClient::new(settings.openai_api_key.clone(), http_client.clone());
+
+    if let Some(config) = settings.discord_bot.clone() {
+        let http_client = http_client.clone();
+        tokio::spawn(async move {
+            match discord_bot::register_commands(&config, &http_client).await {
+                Ok(()) => tracing::info!("registered discord commands"),
+                Err(e) => tracing::warn!("error registering discord commands: {e}"),
+            }
+        });
+    }
+
+    let offer_events_cancellation_token = CancellationToken::default();
+    let offer_event_broker =
+        OfferEventBroker::listen(&db, offer_events_cancellation_token.clone()).await?;
 
     let schema = FinalSchema::build(
         QueryRoot::default(),
         MutationRoot::default(),
-        EmptySubscription,
+        SubscriptionRoot::default(),
     )
     .data(event_manager.clone())
     .data(http_client.clone())
@@ -212,6 +267,9 @@ async fn main() -> Result<(), anyhow::Error> {
     .data(basic_http_client)
     .data(settings.clone())
     .data(db.clone())
+    .data(geocoder)
+    .data(openai_api_client)
+    .data(offer_event_broker)
     .data(DataLoader::new(
         OfferCountDataLoader {
             database: db.clone(),
@@ -226,6 +284,38 @@ async fn main() -> Result<(), anyhow::Error> {
         },
         tokio::spawn,
     ))
+    .data(DataLoader::new(
+        OfferProductSetsLoader {
+            database: db.clone(),
+        },
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        ProductLoader {
+            database: db.clone(),
+        },
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        ProductPriceLoader {
+            database: db.clone(),
+        },
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        OfferHistoryLoader {
+            database: db.clone(),
+        },
+        tokio::spawn,
+    ))
+    .data(DataLoader::new(
+        DealStackCodeLoader {
+            database: db.clone(),
+            settings: settings.clone(),
+            codes: Default::default(),
+        },
+        tokio::spawn,
+    ))
     .data(DataLoader::new(
         LocationLoader {
             database: db,
@@ -243,16 +333,24 @@ async fn main() -> Result<(), anyhow::Error> {
 
     let api_routes = Router::new()
         .route("/graphql", get(graphiql).post(graphql_handler))
+        .route("/graphql/ws", get(graphql_ws_handler))
         // admin only
         .route("/event", post(create_event))
         .route("/event/bulk", post(create_bulk_events))
         .route("/event", get(get_events_history))
         .route("/event/all", get(get_events))
+        .route("/event/deliveries", get(get_notification_deliveries))
+        .route(
+            "/event/deliveries/{id}/replay",
+            post(replay_notification_delivery),
+        )
         .layer(OtelInResponseLayer)
         .layer(OtelAxumLayer::default())
         // open
         .route("/health", get(health))
         .route("/health/self", get(self_health))
+        // signed by discord
+        .route("/discord/interactions", post(discord_bot::interactions))
         .layer(cors)
         .with_state(ApiState {
             schema,
@@ -271,6 +369,7 @@ async fn main() -> Result<(), anyhow::Error> {
         .with_graceful_shutdown(base::shutdown::axum_shutdown_signal())
         .await?;
 
+    offer_events_cancellation_token.cancel();
     job_executor_cancellation_token.cancel();
     job_executor.shutdown().await;
     cancellation_token.cancel();
@@ -0,0 +1,69 @@
+use super::{ChannelError, NewOfferNotification, NotificationChannel, check_response, fingerprint};
+use crate::discord_webhook::DiscordWebhookMessage;
+use base::constants::IMAGE_BASE_URL;
+use chrono::TimeDelta;
+use reqwest::{StatusCode, header::CONTENT_TYPE};
+use reqwest_middleware::ClientWithMiddleware;
+use serde_json::Value;
+use twilight_model::util::Timestamp;
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, ImageSource};
+
+pub struct DiscordChannel {
+    pub url: String,
+}
+
+#[async_trait::async_trait]
+impl NotificationChannel for DiscordChannel {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    fn target(&self) -> String {
+        fingerprint(&self.url)
+    }
+
+    // webhooks allow 30 messages a minute per channel
+    fn min_interval(&self) -> TimeDelta {
+        TimeDelta::seconds(2)
+    }
+
+    fn format(&self, notification: &NewOfferNotification) -> Result<Value, ChannelError> {
+        let details = &notification.details;
+        let embed = EmbedBuilder::new()
+            .color(0xDA291C)
+            .title("New Deal")
+            .field(EmbedFieldBuilder::new("Name", &details.short_name));
+
+        let embed = match Timestamp::from_secs(details.created_at.and_utc().timestamp()) {
+            Ok(timestamp) => embed.timestamp(timestamp),
+            Err(_) => embed,
+        };
+
+        let embed = match ImageSource::url(notification.image_url()) {
+            Ok(image) => embed.thumbnail(image),
+            Err(_) => embed,
+        }
+        .build();
+
+        let mut webhook_message =
+            DiscordWebhookMessage::new("Maccas".to_owned(), format!("{IMAGE_BASE_URL}/og.png"));
+        let webhook_message = webhook_message.add_embed(embed);
+
+        Ok(serde_json::to_value(webhook_message)?)
+    }
+
+    async fn send(
+        &self,
+        http_client: &ClientWithMiddleware,
+        body: &Value,
+    ) -> Result<StatusCode, ChannelError> {
+        let response = http_client
+            .post(self.url.as_str())
+            .header(CONTENT_TYPE, "application/json")
+            .json(body)
+            .send()
+            .await?;
+
+        check_response(response).await
+    }
+}
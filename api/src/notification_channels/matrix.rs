@@ -0,0 +1,78 @@
+use super::{ChannelError, NewOfferNotification, NotificationChannel, check_response};
+use chrono::TimeDelta;
+use reqwest::StatusCode;
+use reqwest_middleware::ClientWithMiddleware;
+use sea_orm::prelude::Uuid;
+use serde_json::{Value, json};
+
+pub struct MatrixChannel {
+    pub homeserver_url: String,
+    pub access_token: String,
+    pub room_id: String,
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[async_trait::async_trait]
+impl NotificationChannel for MatrixChannel {
+    fn name(&self) -> &'static str {
+        "matrix"
+    }
+
+    fn target(&self) -> String {
+        self.room_id.clone()
+    }
+
+    // homeservers rate limit bot accounts per second by default
+    fn min_interval(&self) -> TimeDelta {
+        TimeDelta::seconds(1)
+    }
+
+    fn format(&self, notification: &NewOfferNotification) -> Result<Value, ChannelError> {
+        let short_name = &notification.details.short_name;
+        Ok(json!({
+            "msgtype": "m.text",
+            "body": format!("New Deal: {short_name}"),
+            "format": "org.matrix.custom.html",
+            "formatted_body": format!("<b>New Deal</b>: {}", escape_html(short_name)),
+        }))
+    }
+
+    async fn send(
+        &self,
+        http_client: &ClientWithMiddleware,
+        body: &Value,
+    ) -> Result<StatusCode, ChannelError> {
+        let mut url = reqwest::Url::parse(&self.homeserver_url)
+            .map_err(|e| ChannelError::InvalidConfig(e.to_string()))?;
+
+        // room ids start with ! and contain a :, path_segments_mut encodes them
+        let txn_id = Uuid::new_v4().to_string();
+        url.path_segments_mut()
+            .map_err(|_| ChannelError::InvalidConfig("homeserver_url can't be a base".to_owned()))?
+            .pop_if_empty()
+            .extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                &self.room_id,
+                "send",
+                "m.room.message",
+                &txn_id,
+            ]);
+
+        let response = http_client
+            .put(url)
+            .bearer_auth(&self.access_token)
+            .json(body)
+            .send()
+            .await?;
+
+        check_response(response).await
+    }
+}
@@ -0,0 +1,441 @@
+use self::{
+    discord::DiscordChannel, matrix::MatrixChannel, ntfy::NtfyChannel, slack::SlackChannel,
+    telegram::TelegramChannel, webhook::WebhookChannel,
+};
+use crate::{
+    event_manager::{EventManager, EventManagerError},
+    settings::{NewOffer, Settings},
+};
+use api::Event;
+use base::constants::{IMAGE_BASE_URL, IMAGE_EXT};
+use chrono::TimeDelta;
+use entity::{
+    notification_deliveries, offer_details, offers, sea_orm_active_enums::DeliveryStatus,
+};
+use opentelemetry::trace::TraceContextExt;
+use reqwest::StatusCode;
+use reqwest_middleware::ClientWithMiddleware;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, Set, Statement, TransactionTrait,
+};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+
+mod discord;
+mod matrix;
+mod ntfy;
+mod slack;
+mod telegram;
+pub mod webhook;
+
+// error bodies from a channel can be whole html pages
+const MAX_ERROR_BODY: usize = 500;
+// the first send plus 4 retries
+const MAX_ATTEMPTS: i32 = 5;
+const RETRY_BASE_DELAY: TimeDelta = TimeDelta::minutes(1);
+
+#[derive(serde::Serialize)]
+pub struct NewOfferNotification {
+    pub details: offer_details::Model,
+    pub offer: offers::Model,
+}
+
+impl NewOfferNotification {
+    pub fn image_url(&self) -> String {
+        format!(
+            "{IMAGE_BASE_URL}/{}.{IMAGE_EXT}",
+            self.details.image_base_name
+        )
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ChannelError {
+    #[error("request error: {0}")]
+    Request(reqwest_middleware::Error),
+    #[error("serializer error: {0}")]
+    Serializer(#[from] serde_json::Error),
+    #[error("rejected with {status}: {body}")]
+    Rejected { status: StatusCode, body: String },
+    #[error("invalid channel config: {0}")]
+    InvalidConfig(String),
+}
+
+// reqwest puts the url in the error message, and for most channels the url is the credential.
+// these end up in the logs and in notification_deliveries
+impl From<reqwest_middleware::Error> for ChannelError {
+    fn from(e: reqwest_middleware::Error) -> Self {
+        ChannelError::Request(e.without_url())
+    }
+}
+
+impl ChannelError {
+    fn response_status(&self) -> Option<i32> {
+        match self {
+            ChannelError::Rejected { status, .. } => Some(status.as_u16().into()),
+            _ => None,
+        }
+    }
+
+    // anything else would be rejected the same way again
+    fn is_retryable(&self) -> bool {
+        match self {
+            ChannelError::Request(_) => true,
+            ChannelError::Rejected { status, .. } => {
+                *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+            }
+            ChannelError::Serializer(_) | ChannelError::InvalidConfig(_) => false,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+pub trait NotificationChannel: Send + Sync {
+    fn name(&self) -> &'static str;
+    // where the notification went, without any tokens that are part of the url
+    fn target(&self) -> String;
+    // smallest gap between two deliveries to the same target
+    fn min_interval(&self) -> TimeDelta;
+    // the request body, stored with the delivery so a retry sends exactly the same thing
+    fn format(&self, notification: &NewOfferNotification) -> Result<Value, ChannelError>;
+    async fn send(
+        &self,
+        http_client: &ClientWithMiddleware,
+        body: &Value,
+    ) -> Result<StatusCode, ChannelError>;
+}
+
+pub fn from_settings(
+    config: &NewOffer,
+    external_webhook_secret: &str,
+) -> Vec<Box<dyn NotificationChannel>> {
+    let mut channels: Vec<Box<dyn NotificationChannel>> = vec![];
+
+    for url in &config.discord_urls {
+        channels.push(Box::new(DiscordChannel { url: url.clone() }));
+    }
+    for url in &config.external_urls {
+        channels.push(Box::new(WebhookChannel {
+            url: url.clone(),
+            secret: external_webhook_secret.to_owned(),
+        }));
+    }
+    for url in &config.slack_urls {
+        channels.push(Box::new(SlackChannel { url: url.clone() }));
+    }
+    if let Some(telegram) = &config.telegram {
+        for chat_id in &telegram.chat_ids {
+            channels.push(Box::new(TelegramChannel {
+                bot_token: telegram.bot_token.clone(),
+                chat_id: chat_id.clone(),
+            }));
+        }
+    }
+    if let Some(ntfy) = &config.ntfy {
+        for topic in &ntfy.topics {
+            channels.push(Box::new(NtfyChannel {
+                base_url: ntfy.base_url.clone(),
+                topic: topic.clone(),
+                access_token: ntfy.access_token.clone(),
+            }));
+        }
+    }
+    if let Some(matrix) = &config.matrix {
+        for room_id in &matrix.room_ids {
+            channels.push(Box::new(MatrixChannel {
+                homeserver_url: matrix.homeserver_url.clone(),
+                access_token: matrix.access_token.clone(),
+                room_id: room_id.clone(),
+            }));
+        }
+    }
+
+    channels
+}
+
+// what send_attempt did
+enum Attempt {
+    Sent(notification_deliveries::Model),
+    // nothing was sent, the target's rate limit allows another send after this long
+    RateLimited(Duration),
+}
+
+pub async fn deliver(
+    em: &EventManager,
+    channel: &dyn NotificationChannel,
+    notification: &NewOfferNotification,
+) -> Result<(), EventManagerError> {
+    let body = match channel.format(notification) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::warn!("error formatting for {}: {e}", channel.name());
+            return Ok(());
+        }
+    };
+
+    send_first_attempt(em, channel, notification.details.proposition_id, body).await
+}
+
+// a first send that was rate limited, the body was formatted when the offer was found
+pub async fn deliver_deferred(
+    em: &EventManager,
+    channel_name: &str,
+    target: &str,
+    offer_proposition_id: i64,
+    body: Value,
+) -> Result<(), EventManagerError> {
+    let Some(channel) = configured_channel(em, channel_name, target) else {
+        tracing::warn!("{channel_name} {target} is no longer configured");
+        return Ok(());
+    };
+
+    send_first_attempt(em, channel.as_ref(), offer_proposition_id, body).await
+}
+
+async fn send_first_attempt(
+    em: &EventManager,
+    channel: &dyn NotificationChannel,
+    offer_proposition_id: i64,
+    body: Value,
+) -> Result<(), EventManagerError> {
+    match send_attempt(em, channel, offer_proposition_id, body.clone(), 1, None).await? {
+        Attempt::Sent(delivery) => schedule_retry(em, delivery).await,
+        Attempt::RateLimited(wait) => {
+            tracing::info!("deferring send to {} by {wait:?}", channel.name());
+            create_event(
+                em,
+                Event::DeliverNotification {
+                    channel: channel.name().to_owned(),
+                    target: channel.target(),
+                    offer_proposition_id,
+                    body,
+                },
+                wait,
+            )
+            .await
+        }
+    }
+}
+
+// used for both scheduled retries and replays, a delivery is only ever retried once
+pub async fn redeliver(em: &EventManager, delivery_id: i32) -> Result<(), EventManagerError> {
+    let db = em.db();
+    let Some(delivery) = notification_deliveries::Entity::find_by_id(delivery_id)
+        .one(db)
+        .await?
+    else {
+        tracing::warn!("delivery {delivery_id} not found");
+        return Ok(());
+    };
+
+    let retried = notification_deliveries::Entity::find()
+        .filter(notification_deliveries::Column::RetryOf.eq(delivery_id))
+        .count(db)
+        .await?;
+    if retried > 0 {
+        tracing::info!("delivery {delivery_id} was already retried");
+        return Ok(());
+    }
+
+    let Some(body) = delivery.request_body.clone() else {
+        tracing::warn!("delivery {delivery_id} has no request body to send");
+        return Ok(());
+    };
+
+    let Some(channel) = configured_channel(em, &delivery.channel, &delivery.target) else {
+        tracing::warn!(
+            "{} {} is no longer configured",
+            delivery.channel,
+            delivery.target
+        );
+        return Ok(());
+    };
+
+    let retry = send_attempt(
+        em,
+        channel.as_ref(),
+        delivery.offer_proposition_id,
+        body,
+        delivery.attempt + 1,
+        Some(delivery.id),
+    )
+    .await?;
+
+    match retry {
+        Attempt::Sent(retry) => schedule_retry(em, retry).await,
+        Attempt::RateLimited(wait) => {
+            tracing::info!("deferring retry of delivery {delivery_id} by {wait:?}");
+            create_event(em, Event::RetryNotificationDelivery { delivery_id }, wait).await
+        }
+    }
+}
+
+fn configured_channel(
+    em: &EventManager,
+    name: &str,
+    target: &str,
+) -> Option<Box<dyn NotificationChannel>> {
+    let settings = em.get_state::<Settings>();
+    from_settings(&settings.new_offer, &settings.external_webhook_secret)
+        .into_iter()
+        .find(|c| c.name() == name && c.target() == target)
+}
+
+// sends and records the outcome, unless the target was sent to less than the channel's
+// min_interval ago. a failed send is only logged so the other channels still get the offer
+async fn send_attempt(
+    em: &EventManager,
+    channel: &dyn NotificationChannel,
+    offer_proposition_id: i64,
+    body: Value,
+    attempt: i32,
+    retry_of: Option<i32>,
+) -> Result<Attempt, DbErr> {
+    let db = em.db();
+    let http_client = em.get_state::<ClientWithMiddleware>();
+    let target = channel.target();
+
+    // held until the delivery is inserted, so concurrent sends to a target queue up here
+    // instead of all reading the same last delivery
+    let txn = db.begin().await?;
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT pg_advisory_xact_lock(hashtext($1))",
+        [format!("notification:{}:{target}", channel.name()).into()],
+    ))
+    .await?;
+
+    let last_delivery = notification_deliveries::Entity::find()
+        .filter(notification_deliveries::Column::Channel.eq(channel.name()))
+        .filter(notification_deliveries::Column::Target.eq(&target))
+        .order_by_desc(notification_deliveries::Column::Id)
+        .one(&txn)
+        .await?;
+
+    if let Some(last_delivery) = last_delivery {
+        let next_allowed = last_delivery.created_at + channel.min_interval();
+        let now = chrono::offset::Utc::now().naive_utc();
+        if let Ok(wait) = (next_allowed - now).to_std()
+            && !wait.is_zero()
+        {
+            return Ok(Attempt::RateLimited(wait));
+        }
+    }
+
+    // the column default is the transaction's start, which can be well before the lock
+    let sent_at = chrono::offset::Utc::now().naive_utc();
+    let started_at = Instant::now();
+    let result = channel.send(http_client, &body).await;
+    let latency_ms = i32::try_from(started_at.elapsed().as_millis()).unwrap_or(i32::MAX);
+
+    let (status, response_status, error) = match &result {
+        Ok(status) => (
+            DeliveryStatus::Delivered,
+            Some(status.as_u16().into()),
+            None,
+        ),
+        Err(e) => {
+            tracing::warn!("error sending to {} {target}: {e}", channel.name());
+            (
+                DeliveryStatus::Failed,
+                e.response_status(),
+                Some(e.to_string()),
+            )
+        }
+    };
+
+    // the retry decision is made here while the error is still around
+    let next_attempt_at = match result {
+        Err(e) if e.is_retryable() && attempt < MAX_ATTEMPTS => {
+            Some(chrono::offset::Utc::now().naive_utc() + retry_delay(attempt))
+        }
+        _ => None,
+    };
+
+    let delivery = notification_deliveries::ActiveModel {
+        channel: Set(channel.name().to_owned()),
+        target: Set(target),
+        offer_proposition_id: Set(offer_proposition_id),
+        status: Set(status),
+        response_status: Set(response_status),
+        error: Set(error),
+        request_body: Set(Some(body)),
+        latency_ms: Set(Some(latency_ms)),
+        attempt: Set(attempt),
+        retry_of: Set(retry_of),
+        next_attempt_at: Set(next_attempt_at),
+        created_at: Set(sent_at),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(Attempt::Sent(delivery))
+}
+
+// 1, 2, 4 then 8 minutes
+fn retry_delay(attempt: i32) -> TimeDelta {
+    RETRY_BASE_DELAY * 2_i32.pow(u32::try_from(attempt - 1).unwrap_or_default())
+}
+
+async fn schedule_retry(
+    em: &EventManager,
+    delivery: notification_deliveries::Model,
+) -> Result<(), EventManagerError> {
+    let Some(next_attempt_at) = delivery.next_attempt_at else {
+        return Ok(());
+    };
+
+    let delay = (next_attempt_at - chrono::offset::Utc::now().naive_utc())
+        .to_std()
+        .unwrap_or_default();
+
+    create_event(
+        em,
+        Event::RetryNotificationDelivery {
+            delivery_id: delivery.id,
+        },
+        delay,
+    )
+    .await
+}
+
+async fn create_event(
+    em: &EventManager,
+    event: Event,
+    delay: Duration,
+) -> Result<(), EventManagerError> {
+    let trace_id = opentelemetry::Context::current()
+        .span()
+        .span_context()
+        .trace_id()
+        .to_string();
+
+    em.create_event(event, delay, trace_id).await?;
+
+    Ok(())
+}
+
+async fn check_response(response: reqwest::Response) -> Result<StatusCode, ChannelError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(status);
+    }
+
+    let mut body = response.text().await.unwrap_or_default();
+    if let Some((end, _)) = body.char_indices().nth(MAX_ERROR_BODY) {
+        body.truncate(end);
+    }
+
+    Err(ChannelError::Rejected { status, body })
+}
+
+// for urls where the path is the credential
+fn fingerprint(url: &str) -> String {
+    let digest = Sha256::digest(url.as_bytes());
+    hex::encode(&digest[..6])
+}
@@ -0,0 +1,51 @@
+use super::{ChannelError, NewOfferNotification, NotificationChannel, check_response};
+use chrono::TimeDelta;
+use reqwest::StatusCode;
+use reqwest_middleware::ClientWithMiddleware;
+use serde_json::{Value, json};
+
+pub struct NtfyChannel {
+    pub base_url: String,
+    pub topic: String,
+    pub access_token: Option<String>,
+}
+
+#[async_trait::async_trait]
+impl NotificationChannel for NtfyChannel {
+    fn name(&self) -> &'static str {
+        "ntfy"
+    }
+
+    fn target(&self) -> String {
+        format!("{}/{}", self.base_url.trim_end_matches('/'), self.topic)
+    }
+
+    // ntfy.sh refills a visitor's burst at one message every 5 seconds
+    fn min_interval(&self) -> TimeDelta {
+        TimeDelta::seconds(5)
+    }
+
+    // publishing as json to the root lets the topic and attachment go in the body
+    fn format(&self, notification: &NewOfferNotification) -> Result<Value, ChannelError> {
+        Ok(json!({
+            "topic": self.topic,
+            "title": "New Deal",
+            "message": notification.details.short_name,
+            "attach": notification.image_url(),
+            "tags": ["hamburger"],
+        }))
+    }
+
+    async fn send(
+        &self,
+        http_client: &ClientWithMiddleware,
+        body: &Value,
+    ) -> Result<StatusCode, ChannelError> {
+        let mut request = http_client.post(self.base_url.as_str()).json(body);
+        if let Some(access_token) = &self.access_token {
+            request = request.bearer_auth(access_token);
+        }
+
+        check_response(request.send().await?).await
+    }
+}
@@ -0,0 +1,68 @@
+use super::{ChannelError, NewOfferNotification, NotificationChannel, check_response, fingerprint};
+use chrono::TimeDelta;
+use reqwest::StatusCode;
+use reqwest_middleware::ClientWithMiddleware;
+use serde_json::{Value, json};
+
+pub struct SlackChannel {
+    pub url: String,
+}
+
+// the only characters mrkdwn needs escaped
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[async_trait::async_trait]
+impl NotificationChannel for SlackChannel {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    fn target(&self) -> String {
+        fingerprint(&self.url)
+    }
+
+    // incoming webhooks allow one message a second
+    fn min_interval(&self) -> TimeDelta {
+        TimeDelta::seconds(1)
+    }
+
+    fn format(&self, notification: &NewOfferNotification) -> Result<Value, ChannelError> {
+        let details = &notification.details;
+        let short_name = escape(&details.short_name);
+
+        // text is the fallback for notifications and clients without blocks
+        Ok(json!({
+            "text": format!("New Deal: {short_name}"),
+            "blocks": [{
+                "type": "section",
+                "text": {
+                    "type": "mrkdwn",
+                    "text": format!("*New Deal*\n{short_name}\n{}", escape(&details.description)),
+                },
+                "accessory": {
+                    "type": "image",
+                    "image_url": notification.image_url(),
+                    "alt_text": details.short_name,
+                },
+            }],
+        }))
+    }
+
+    async fn send(
+        &self,
+        http_client: &ClientWithMiddleware,
+        body: &Value,
+    ) -> Result<StatusCode, ChannelError> {
+        let response = http_client
+            .post(self.url.as_str())
+            .json(body)
+            .send()
+            .await?;
+
+        check_response(response).await
+    }
+}
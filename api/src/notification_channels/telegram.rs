@@ -0,0 +1,54 @@
+use super::{ChannelError, NewOfferNotification, NotificationChannel, check_response};
+use chrono::TimeDelta;
+use reqwest::StatusCode;
+use reqwest_middleware::ClientWithMiddleware;
+use serde_json::{Value, json};
+
+const TELEGRAM_API_BASE: &str = "https://api.telegram.org";
+
+pub struct TelegramChannel {
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+#[async_trait::async_trait]
+impl NotificationChannel for TelegramChannel {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn target(&self) -> String {
+        self.chat_id.clone()
+    }
+
+    // bots can send 20 messages a minute to a group
+    fn min_interval(&self) -> TimeDelta {
+        TimeDelta::seconds(3)
+    }
+
+    fn format(&self, notification: &NewOfferNotification) -> Result<Value, ChannelError> {
+        // plain caption, no parse_mode so offer names don't need escaping
+        Ok(json!({
+            "chat_id": self.chat_id,
+            "photo": notification.image_url(),
+            "caption": format!("New Deal: {}", notification.details.short_name),
+        }))
+    }
+
+    async fn send(
+        &self,
+        http_client: &ClientWithMiddleware,
+        body: &Value,
+    ) -> Result<StatusCode, ChannelError> {
+        let response = http_client
+            .post(format!(
+                "{TELEGRAM_API_BASE}/bot{}/sendPhoto",
+                self.bot_token
+            ))
+            .json(body)
+            .send()
+            .await?;
+
+        check_response(response).await
+    }
+}
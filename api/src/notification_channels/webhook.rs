@@ -0,0 +1,70 @@
+use super::{ChannelError, NewOfferNotification, NotificationChannel, check_response, fingerprint};
+use chrono::TimeDelta;
+use hmac::{Hmac, Mac};
+use reqwest::{StatusCode, header::CONTENT_TYPE};
+use reqwest_middleware::ClientWithMiddleware;
+use serde_json::Value;
+use sha2::Sha256;
+
+pub const SIGNATURE_HEADER: &str = "X-Maccas-Signature";
+
+pub struct WebhookChannel {
+    pub url: String,
+    pub secret: String,
+}
+
+// hex encoded HMAC-SHA256 of "{timestamp}.{body}". the timestamp is signed too so a
+// receiver can reject old requests instead of accepting a captured one forever
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> Result<String, ChannelError> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|e| ChannelError::InvalidConfig(e.to_string()))?;
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+#[async_trait::async_trait]
+impl NotificationChannel for WebhookChannel {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    // receivers often take a token in the query string or path
+    fn target(&self) -> String {
+        fingerprint(&self.url)
+    }
+
+    // our own receivers, nothing to stay under
+    fn min_interval(&self) -> TimeDelta {
+        TimeDelta::zero()
+    }
+
+    fn format(&self, notification: &NewOfferNotification) -> Result<Value, ChannelError> {
+        Ok(serde_json::to_value(notification)?)
+    }
+
+    async fn send(
+        &self,
+        http_client: &ClientWithMiddleware,
+        body: &Value,
+    ) -> Result<StatusCode, ChannelError> {
+        // signed per attempt, a retry gets a fresh timestamp
+        let body = serde_json::to_vec(body)?;
+        let timestamp = chrono::offset::Utc::now().timestamp();
+        let signature = sign(&self.secret, timestamp, &body)?;
+
+        // no plaintext secret header, the secret is the signing key so anyone who saw it on
+        // one request could sign their own
+        let response = http_client
+            .post(self.url.as_str())
+            .header(CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, format!("t={timestamp},v1={signature}"))
+            .body(body)
+            .send()
+            .await?;
+
+        check_response(response).await
+    }
+}
@@ -0,0 +1,173 @@
+use async_graphql::{Enum, SimpleObject};
+use entity::offers;
+use futures::Stream;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, QueryFilter,
+    Statement, prelude::Uuid, sqlx::postgres::PgListener,
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+
+const OFFER_EVENTS_CHANNEL: &str = "offer_events";
+// slow subscribers skip ahead rather than hold everyone else up
+const BROADCAST_CAPACITY: usize = 1024;
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum OfferRemovedReason {
+    Locked,
+    Expired,
+}
+
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct OfferChange {
+    pub offer_id: Uuid,
+    pub offer_proposition_id: i64,
+    pub reason: Option<OfferRemovedReason>,
+}
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum DealStackAction {
+    Added,
+    Removed,
+    LikelyUsed,
+}
+
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct DealStackChange {
+    pub transaction_id: Uuid,
+    pub offer_proposition_id: i64,
+    pub action: DealStackAction,
+}
+
+#[derive(SimpleObject, Clone, Debug, Serialize, Deserialize)]
+pub struct WaitlistNotification {
+    #[graphql(skip)]
+    pub user_id: Uuid,
+    pub offer_proposition_id: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum OfferEvent {
+    OfferAdded(OfferChange),
+    OfferRemoved(OfferChange),
+    DealStackChanged(DealStackChange),
+    WaitlistNotified(WaitlistNotification),
+}
+
+impl OfferEvent {
+    pub fn added(offer: &offers::Model) -> Self {
+        OfferEvent::OfferAdded(OfferChange {
+            offer_id: offer.id,
+            offer_proposition_id: offer.offer_proposition_id,
+            reason: None,
+        })
+    }
+
+    pub fn removed(offer: &offers::Model, reason: OfferRemovedReason) -> Self {
+        OfferEvent::OfferRemoved(OfferChange {
+            offer_id: offer.id,
+            offer_proposition_id: offer.offer_proposition_id,
+            reason: Some(reason),
+        })
+    }
+}
+
+// NOTIFY inside a transaction is only delivered on commit
+pub async fn publish<C>(db: &C, event: &OfferEvent) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    let payload = serde_json::to_string(event).map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    db.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT pg_notify($1, $2)",
+        [OFFER_EVENTS_CHANNEL.into(), payload.into()],
+    ))
+    .await?;
+
+    Ok(())
+}
+
+// every offer currently held by the account, for when it is locked or unlocked
+pub async fn publish_for_account<C, F>(db: &C, account_id: Uuid, to_event: F) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+    F: Fn(&offers::Model) -> OfferEvent,
+{
+    let offers = offers::Entity::find()
+        .filter(offers::Column::AccountId.eq(account_id))
+        .all(db)
+        .await?;
+
+    for offer in &offers {
+        publish(db, &to_event(offer)).await?;
+    }
+
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct OfferEventBroker {
+    sender: broadcast::Sender<OfferEvent>,
+}
+
+impl OfferEventBroker {
+    // one LISTEN connection per process, fanned out to every subscriber
+    pub async fn listen(
+        db: &DatabaseConnection,
+        cancellation_token: CancellationToken,
+    ) -> Result<Self, sea_orm::sqlx::Error> {
+        let pool = db.get_postgres_connection_pool().clone();
+        let mut listener = PgListener::connect_with(&pool).await?;
+        listener.listen(OFFER_EVENTS_CHANNEL).await?;
+
+        let (sender, _) = broadcast::channel(BROADCAST_CAPACITY);
+        let broker = Self {
+            sender: sender.clone(),
+        };
+
+        tokio::spawn(async move {
+            loop {
+                let notification = tokio::select! {
+                    _ = cancellation_token.cancelled() => break,
+                    n = listener.recv() => n,
+                };
+
+                match notification {
+                    Ok(n) => match serde_json::from_str::<OfferEvent>(n.payload()) {
+                        Ok(event) => {
+                            // only fails when nobody is subscribed
+                            let _ = sender.send(event);
+                        }
+                        Err(e) => tracing::warn!("unreadable offer event: {e}"),
+                    },
+                    // recv reconnects on the next call
+                    Err(e) => {
+                        tracing::error!("offer event listener error: {e}");
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                }
+            }
+        });
+
+        Ok(broker)
+    }
+
+    pub fn subscribe(&self) -> impl Stream<Item = OfferEvent> + use<> {
+        futures::stream::unfold(self.sender.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("subscriber lagged, skipped {skipped} offer events");
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,290 @@
+use crate::settings;
+use entity::push_subscriptions;
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest_middleware::ClientWithMiddleware;
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, prelude::Uuid};
+use serde::Serialize;
+use web_push::{
+    ContentEncoding, PartialVapidSignatureBuilder, SubscriptionInfo, VapidSignatureBuilder,
+    WebPushError, WebPushMessageBuilder,
+};
+
+// a deal notification isn't worth delivering once it's this old
+const TTL_SECONDS: u32 = 12 * 60 * 60;
+// endpoints are posted to from inside the network, so only the browsers' push services
+// are allowed, the host or any subdomain of it
+const PUSH_SERVICE_HOSTS: &[&str] = &[
+    "fcm.googleapis.com",
+    "android.googleapis.com",
+    "push.services.mozilla.com",
+    "push.apple.com",
+    "notify.windows.com",
+];
+// base64url without padding, an uncompressed p-256 point and a 16 byte secret
+const P256DH_LENGTH: usize = 87;
+const AUTH_LENGTH: usize = 22;
+
+// what the service worker receives in the push event
+#[derive(Serialize, Debug)]
+pub struct PushNotification {
+    pub title: String,
+    pub body: String,
+    // replaces an earlier notification with the same tag on the device
+    pub tag: String,
+    pub image: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum WebPushSendError {
+    #[error("web push error: {0}")]
+    WebPush(#[from] WebPushError),
+    #[error("serializer error: {0}")]
+    Serializer(#[from] serde_json::Error),
+    #[error("request error: {0}")]
+    Request(#[from] reqwest_middleware::Error),
+    #[error("push service responded with {0}")]
+    Rejected(StatusCode),
+    #[error("endpoint is not a known push service")]
+    UnknownPushService,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum InvalidSubscription {
+    #[error("endpoint must be an https url on a push service")]
+    Endpoint,
+    #[error("p256dh must be a base64url encoded p-256 public key")]
+    P256dh,
+    #[error("auth must be a base64url encoded 16 byte secret")]
+    Auth,
+}
+
+pub fn is_push_service(endpoint: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(endpoint) else {
+        return false;
+    };
+    // ip addresses have no domain
+    let Some(host) = url.domain() else {
+        return false;
+    };
+
+    url.scheme() == "https"
+        && url.port().is_none()
+        && url.username().is_empty()
+        && url.password().is_none()
+        && PUSH_SERVICE_HOSTS.iter().any(|service| {
+            host == *service
+                || host
+                    .strip_suffix(service)
+                    .is_some_and(|subdomain| subdomain.ends_with('.'))
+        })
+}
+
+// browsers hand the keys over unpadded, padding is tolerated anyway
+fn is_base64url(value: &str, length: usize) -> bool {
+    let value = value.trim_end_matches('=');
+    value.len() == length
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+pub fn validate_subscription(
+    endpoint: &str,
+    p256dh: &str,
+    auth: &str,
+) -> Result<(), InvalidSubscription> {
+    if !is_push_service(endpoint) {
+        return Err(InvalidSubscription::Endpoint);
+    }
+    if !is_base64url(p256dh, P256DH_LENGTH) {
+        return Err(InvalidSubscription::P256dh);
+    }
+    if !is_base64url(auth, AUTH_LENGTH) {
+        return Err(InvalidSubscription::Auth);
+    }
+
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct WebPushSender {
+    http_client: ClientWithMiddleware,
+    signer: PartialVapidSignatureBuilder,
+    subject: String,
+}
+
+impl WebPushSender {
+    pub fn new(
+        config: &settings::WebPush,
+        http_client: ClientWithMiddleware,
+    ) -> Result<Self, WebPushError> {
+        Ok(Self {
+            http_client,
+            // the key pair generated for the browser's applicationServerKey is url safe
+            signer: VapidSignatureBuilder::from_base64_no_sub(
+                &config.vapid_private_key,
+                web_push::URL_SAFE_NO_PAD,
+            )?,
+            subject: config.subject.clone(),
+        })
+    }
+
+    async fn send(
+        &self,
+        subscription: &push_subscriptions::Model,
+        payload: &[u8],
+    ) -> Result<(), WebPushSendError> {
+        // subscriptions from before endpoints were checked
+        if !is_push_service(&subscription.endpoint) {
+            return Err(WebPushSendError::UnknownPushService);
+        }
+
+        let subscription_info = SubscriptionInfo::new(
+            &subscription.endpoint,
+            &subscription.p256dh,
+            &subscription.auth,
+        );
+
+        let mut signature = self.signer.clone().add_sub_info(&subscription_info);
+        signature.add_claim("sub", self.subject.as_str());
+
+        let mut builder = WebPushMessageBuilder::new(&subscription_info);
+        builder.set_payload(ContentEncoding::Aes128Gcm, payload);
+        builder.set_vapid_signature(signature.build()?);
+        builder.set_ttl(TTL_SECONDS);
+        let message = builder.build()?;
+
+        // the authorization header for vapid is one of the crypto headers
+        let mut request = self
+            .http_client
+            .post(message.endpoint.to_string())
+            .header("TTL", message.ttl.to_string());
+        if let Some(payload) = message.payload {
+            request = request
+                .header(CONTENT_ENCODING, payload.content_encoding.to_str())
+                .header(CONTENT_TYPE, "application/octet-stream");
+            for (name, value) in payload.crypto_headers {
+                request = request.header(name, value);
+            }
+            request = request.body(payload.content);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(WebPushSendError::Rejected(response.status()));
+        }
+
+        Ok(())
+    }
+
+    // failures are per subscription and only logged, subscriptions the push service
+    // reports as gone are removed
+    pub async fn send_to_users(
+        &self,
+        db: &DatabaseConnection,
+        user_ids: impl IntoIterator<Item = Uuid>,
+        notification: &PushNotification,
+    ) -> Result<usize, DbErr> {
+        let subscriptions = push_subscriptions::Entity::find()
+            .filter(push_subscriptions::Column::UserId.is_in(user_ids))
+            .all(db)
+            .await?;
+
+        let payload = match serde_json::to_vec(notification) {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::error!("error serializing push notification: {e}");
+                return Ok(0);
+            }
+        };
+
+        let mut delivered = 0;
+        for subscription in subscriptions {
+            match self.send(&subscription, &payload).await {
+                Ok(()) => delivered += 1,
+                Err(
+                    e @ (WebPushSendError::Rejected(StatusCode::NOT_FOUND | StatusCode::GONE)
+                    | WebPushSendError::UnknownPushService),
+                ) => {
+                    tracing::info!("removing push subscription {}: {e}", subscription.id);
+                    push_subscriptions::Entity::delete_by_id(subscription.id)
+                        .exec(db)
+                        .await?;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "error sending push to subscription {}: {e}",
+                        subscription.id
+                    )
+                }
+            }
+        }
+
+        Ok(delivered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InvalidSubscription, is_push_service, validate_subscription};
+
+    const P256DH: &str =
+        "BNcRdreALRFXTkOOUHK1EtK2wtaz5Ry4YfYCA_0QTpQtUbVlUls0VJXg7A8u-Ts1XbjhazAkj7I99e8QcYP7DkM";
+    const AUTH: &str = "tBHItJI5svbpez7KI4CCXg";
+
+    #[test]
+    fn push_services_are_allowed() {
+        assert!(is_push_service(
+            "https://fcm.googleapis.com/fcm/send/abc:def"
+        ));
+        assert!(is_push_service(
+            "https://updates.push.services.mozilla.com/wpush/v2/abc"
+        ));
+        assert!(is_push_service("https://web.push.apple.com/abc"));
+        assert!(is_push_service(
+            "https://wns2-sy3p.notify.windows.com/w/?token=abc"
+        ));
+    }
+
+    #[test]
+    fn other_endpoints_are_rejected() {
+        assert!(!is_push_service("http://fcm.googleapis.com/fcm/send/abc"));
+        assert!(!is_push_service(
+            "https://fcm.googleapis.com:8443/fcm/send/abc"
+        ));
+        assert!(!is_push_service(
+            "https://user@fcm.googleapis.com/fcm/send/abc"
+        ));
+        assert!(!is_push_service(
+            "https://evilfcm.googleapis.com.example.com/"
+        ));
+        assert!(!is_push_service(
+            "https://notfcm.googleapis.com.attacker.net/"
+        ));
+        assert!(!is_push_service("https://attackerpush.apple.com/"));
+        assert!(!is_push_service("https://127.0.0.1/"));
+        assert!(!is_push_service("https://[::1]/"));
+        assert!(!is_push_service("https://localhost/"));
+        assert!(!is_push_service("not a url"));
+    }
+
+    #[test]
+    fn keys_are_checked() {
+        let endpoint = "https://fcm.googleapis.com/fcm/send/abc";
+        assert_eq!(validate_subscription(endpoint, P256DH, AUTH), Ok(()));
+        assert_eq!(
+            validate_subscription(endpoint, &P256DH[1..], AUTH),
+            Err(InvalidSubscription::P256dh)
+        );
+        assert_eq!(
+            validate_subscription(endpoint, P256DH, "tBHItJI5svbpez7KI4CCX+"),
+            Err(InvalidSubscription::Auth)
+        );
+        assert_eq!(
+            validate_subscription("https://example.com/", P256DH, AUTH),
+            Err(InvalidSubscription::Endpoint)
+        );
+    }
+}
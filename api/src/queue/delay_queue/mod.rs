@@ -52,12 +52,28 @@ where
     }
 
     #[instrument(skip(self))]
-    pub async fn push(&self, item: T, delay: Duration) -> Result<(), DelayQueueError> {
-        self.inner
+    pub async fn push(&self, item: T, delay: Duration) -> Result<i64, DelayQueueError> {
+        let message_id = self
+            .inner
             .queue
             .send_delay(&self.inner.queue_name, &item, delay.as_secs() as u32)
             .await?;
 
+        Ok(message_id)
+    }
+
+    // moves the point the message becomes readable to now + delay
+    #[instrument(skip(self))]
+    pub async fn reschedule(
+        &self,
+        message_id: i64,
+        delay: Duration,
+    ) -> Result<(), DelayQueueError> {
+        self.inner
+            .queue
+            .set_vt::<T>(&self.inner.queue_name, message_id, delay.as_secs() as i32)
+            .await?;
+
         Ok(())
     }
 
@@ -0,0 +1,228 @@
+use crate::offer_events::{self, OfferEvent, WaitlistNotification};
+use async_graphql::ErrorExtensions;
+use entity::{account_lock, accounts, offer_waitlist, offers};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, JoinType,
+    QueryFilter, QueryOrder, QuerySelect, QueryTrait, RelationTrait, Set, TransactionTrait,
+    prelude::Uuid,
+    sea_query::{LockBehavior, LockType, OnConflict},
+};
+use std::time::Duration;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ReservationError {
+    #[error("no accounts available for offer {offer_proposition_id}")]
+    NoAccountsAvailable {
+        offer_proposition_id: i64,
+        waitlisted: bool,
+    },
+    #[error(transparent)]
+    DbError(#[from] DbErr),
+}
+
+impl ErrorExtensions for ReservationError {
+    fn extend(&self) -> async_graphql::Error {
+        async_graphql::Error::new(self.to_string()).extend_with(|_, e| match self {
+            ReservationError::NoAccountsAvailable {
+                offer_proposition_id,
+                waitlisted,
+            } => {
+                e.set("code", "NO_ACCOUNTS_AVAILABLE");
+                e.set("offerPropositionId", *offer_proposition_id);
+                e.set("waitlisted", *waitlisted);
+            }
+            ReservationError::DbError(_) => e.set("code", "INTERNAL_SERVER_ERROR"),
+        })
+    }
+}
+
+pub struct Reservation {
+    pub offer: offers::Model,
+    pub account: accounts::Model,
+}
+
+// each attempt skips accounts it already lost, so this only runs out when several
+// reservations race for the same last few accounts
+const MAX_RESERVE_ATTEMPTS: usize = 5;
+// waiting longer than this means the offer isn't coming back any time soon
+const WAITLIST_EXPIRY: Duration = Duration::from_secs(24 * 60 * 60);
+// long enough to see the notification and reserve
+const NOTIFIED_EXPIRY: Duration = Duration::from_secs(15 * 60);
+
+// the account row stays locked until the account_lock insert commits, so a concurrent
+// reservation usually skips to the next account. an account_lock can still appear without
+// the row lock (the lock is inserted elsewhere or left behind), that account is skipped too
+pub async fn reserve_offer(
+    db: &DatabaseConnection,
+    offer_proposition_id: i64,
+    lock_for: Duration,
+) -> Result<Reservation, ReservationError> {
+    let mut skipped = Vec::new();
+
+    for _ in 0..MAX_RESERVE_ATTEMPTS {
+        let txn = db.begin().await?;
+
+        let locked_accounts = account_lock::Entity::find()
+            .select_only()
+            .column(account_lock::Column::Id)
+            .into_query();
+
+        let reserved = offers::Entity::find()
+            .filter(offers::Column::OfferPropositionId.eq(offer_proposition_id))
+            .filter(offers::Column::AccountId.not_in_subquery(locked_accounts))
+            .filter(offers::Column::AccountId.is_not_in(skipped.clone()))
+            .filter(accounts::Column::Active.eq(true))
+            // FOR UPDATE can't lock the nullable side of find_also_related's left join
+            .select_also(accounts::Entity)
+            .join(JoinType::InnerJoin, offers::Relation::Accounts.def())
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .one(&txn)
+            .await?;
+
+        let Some((offer, Some(account))) = reserved else {
+            break;
+        };
+
+        let unlock_at = chrono::offset::Utc::now().naive_utc() + lock_for;
+        let inserted = account_lock::Entity::insert(account_lock::ActiveModel {
+            id: Set(account.id),
+            unlock_at: Set(unlock_at),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::column(account_lock::Column::Id)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(&txn)
+        .await?;
+
+        if inserted == 0 {
+            txn.rollback().await?;
+            skipped.push(account.id);
+            continue;
+        }
+
+        txn.commit().await?;
+
+        return Ok(Reservation { offer, account });
+    }
+
+    Err(ReservationError::NoAccountsAvailable {
+        offer_proposition_id,
+        waitlisted: false,
+    })
+}
+
+pub async fn join_waitlist<C>(db: &C, user_id: Uuid, offer_proposition_id: i64) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    // rejoining keeps the original place in the queue but restarts the expiry
+    offer_waitlist::Entity::insert(offer_waitlist::ActiveModel {
+        user_id: Set(user_id),
+        offer_proposition_id: Set(offer_proposition_id),
+        notified_at: Set(None),
+        created_at: Set(chrono::offset::Utc::now().naive_utc()),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::columns([
+            offer_waitlist::Column::UserId,
+            offer_waitlist::Column::OfferPropositionId,
+        ])
+        .update_columns([
+            offer_waitlist::Column::NotifiedAt,
+            offer_waitlist::Column::CreatedAt,
+        ])
+        .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn leave_waitlist<C>(
+    db: &C,
+    user_id: Uuid,
+    offer_proposition_id: i64,
+) -> Result<bool, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let res = offer_waitlist::Entity::delete_many()
+        .filter(offer_waitlist::Column::UserId.eq(user_id))
+        .filter(offer_waitlist::Column::OfferPropositionId.eq(offer_proposition_id))
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected > 0)
+}
+
+// first in, first notified: one waiter per offer the released account holds
+pub async fn notify_waitlist(db: &DatabaseConnection, account_id: Uuid) -> Result<(), DbErr> {
+    let proposition_ids = offers::Entity::find()
+        .select_only()
+        .column(offers::Column::OfferPropositionId)
+        .filter(offers::Column::AccountId.eq(account_id))
+        .distinct()
+        .into_tuple::<i64>()
+        .all(db)
+        .await?;
+
+    for offer_proposition_id in proposition_ids {
+        let txn = db.begin().await?;
+
+        let waiter = offer_waitlist::Entity::find()
+            .filter(offer_waitlist::Column::OfferPropositionId.eq(offer_proposition_id))
+            .filter(offer_waitlist::Column::NotifiedAt.is_null())
+            .filter(
+                offer_waitlist::Column::CreatedAt
+                    .gt(chrono::offset::Utc::now().naive_utc() - WAITLIST_EXPIRY),
+            )
+            .order_by_asc(offer_waitlist::Column::Id)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .one(&txn)
+            .await?;
+
+        if let Some(waiter) = waiter {
+            offer_waitlist::Entity::update(offer_waitlist::ActiveModel {
+                id: sea_orm::Unchanged(waiter.id),
+                notified_at: Set(Some(chrono::offset::Utc::now().naive_utc())),
+                ..Default::default()
+            })
+            .exec(&txn)
+            .await?;
+
+            let event = OfferEvent::WaitlistNotified(WaitlistNotification {
+                user_id: waiter.user_id,
+                offer_proposition_id,
+            });
+            offer_events::publish(&txn, &event).await?;
+        }
+
+        txn.commit().await?;
+    }
+
+    Ok(())
+}
+
+// waiters that were notified and had their chance, or gave up waiting
+pub async fn prune_waitlist<C>(db: &C) -> Result<u64, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let now = chrono::offset::Utc::now().naive_utc();
+
+    let res = offer_waitlist::Entity::delete_many()
+        .filter(
+            Condition::any()
+                .add(offer_waitlist::Column::NotifiedAt.lte(now - NOTIFIED_EXPIRY))
+                .add(offer_waitlist::Column::CreatedAt.lte(now - WAITLIST_EXPIRY)),
+        )
+        .exec(db)
+        .await?;
+
+    Ok(res.rows_affected)
+}
@@ -1,2 +1,3 @@
 pub mod create_event;
 pub mod get_events;
+pub mod notification_deliveries;
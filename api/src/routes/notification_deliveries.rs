@@ -0,0 +1,77 @@
+use crate::types::{ApiState, AppError};
+use api::{CreateEventResponse, Event, GetNotificationDeliveriesResponse};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use entity::{notification_deliveries, sea_orm_active_enums::DeliveryStatus};
+use opentelemetry::trace::TraceContextExt;
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect};
+use std::time::Duration;
+
+#[derive(serde::Deserialize)]
+pub struct Filter {
+    limit: Option<u64>,
+    channel: Option<String>,
+    status: Option<DeliveryStatus>,
+}
+
+pub async fn get_notification_deliveries(
+    State(ApiState { event_manager, .. }): State<ApiState>,
+    Query(query): Query<Filter>,
+) -> Result<Json<GetNotificationDeliveriesResponse>, AppError> {
+    let mut deliveries = notification_deliveries::Entity::find();
+
+    if let Some(channel) = query.channel {
+        deliveries = deliveries.filter(notification_deliveries::Column::Channel.eq(channel));
+    }
+    if let Some(status) = query.status {
+        deliveries = deliveries.filter(notification_deliveries::Column::Status.eq(status));
+    }
+
+    let deliveries = deliveries
+        .order_by_desc(notification_deliveries::Column::Id)
+        .limit(Some(query.limit.unwrap_or(50)))
+        .all(event_manager.db())
+        .await?;
+
+    Ok(Json(GetNotificationDeliveriesResponse { deliveries }))
+}
+
+pub(crate) async fn replay_notification_delivery(
+    State(ApiState { event_manager, .. }): State<ApiState>,
+    Path(delivery_id): Path<i32>,
+) -> Result<Json<CreateEventResponse>, AppError> {
+    let db = event_manager.db();
+    let Some(delivery) = notification_deliveries::Entity::find_by_id(delivery_id)
+        .one(db)
+        .await?
+    else {
+        return Err(AppError::StatusCode(StatusCode::NOT_FOUND));
+    };
+
+    // replaying anything but the last attempt would send the same offer twice
+    let retried = notification_deliveries::Entity::find()
+        .filter(notification_deliveries::Column::RetryOf.eq(delivery_id))
+        .count(db)
+        .await?;
+    if delivery.status != DeliveryStatus::Failed || retried > 0 {
+        return Err(AppError::StatusCode(StatusCode::CONFLICT));
+    }
+
+    let trace_id = opentelemetry::Context::current()
+        .span()
+        .span_context()
+        .trace_id();
+
+    let id = event_manager
+        .create_event(
+            Event::RetryNotificationDelivery { delivery_id },
+            Duration::ZERO,
+            trace_id.to_string(),
+        )
+        .await?;
+
+    Ok(Json(CreateEventResponse { id }))
+}
@@ -34,21 +34,128 @@ pub struct Email {
     pub domain_name: String,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct Telegram {
+    pub bot_token: String,
+    #[serde(default)]
+    pub chat_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Ntfy {
+    #[serde(default = "Ntfy::default_base_url")]
+    pub base_url: String,
+    #[serde(default)]
+    pub topics: Vec<String>,
+    // for protected topics
+    pub access_token: Option<String>,
+}
+
+impl Ntfy {
+    fn default_base_url() -> String {
+        "https://ntfy.sh".to_owned()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Matrix {
+    pub homeserver_url: String,
+    pub access_token: String,
+    #[serde(default)]
+    pub room_ids: Vec<String>,
+}
+
 #[derive(Debug, Deserialize, Clone, Default)]
 pub struct NewOffer {
     #[serde(default)]
     pub discord_urls: Vec<String>,
     #[serde(default)]
     pub external_urls: Vec<String>,
+    #[serde(default)]
+    pub slack_urls: Vec<String>,
+    pub telegram: Option<Telegram>,
+    pub ntfy: Option<Ntfy>,
+    pub matrix: Option<Matrix>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct MenuCatalog {
+    // stores whose catalog and prices are ingested
+    #[serde(default)]
+    pub store_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GeocoderProvider {
+    #[default]
+    Google,
+    Nominatim,
+    Photon,
+    // offline, matches suburbs and postcodes against the stores table
+    Stores,
 }
 
-impl NewOffer {
-    pub fn should_notify_discord(&self) -> bool {
-        !self.discord_urls.is_empty()
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Geocoder {
+    #[serde(default)]
+    pub provider: GeocoderProvider,
+    // self hosted nominatim or photon, falls back to the public instance
+    pub base_url: Option<String>,
+}
+
+// VAPID keys are base64url encoded, browsers subscribe with the public key
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebPush {
+    pub vapid_public_key: String,
+    pub vapid_private_key: String,
+    // contact for push services, a mailto: or https: url
+    pub subject: String,
+    // how long before a favourited deal's valid_to to warn about it
+    #[serde(default = "WebPush::default_expiry_warning_minutes")]
+    pub expiry_warning_minutes: i64,
+}
+
+impl WebPush {
+    fn default_expiry_warning_minutes() -> i64 {
+        24 * 60
     }
+}
+
+// from the application's page in the discord developer portal
+#[derive(Debug, Deserialize, Clone)]
+pub struct DiscordBot {
+    pub application_id: String,
+    // hex encoded ed25519 key interactions are signed with
+    pub public_key: String,
+    // only used to register the slash commands
+    pub bot_token: String,
+}
 
-    pub fn should_notify_external(&self) -> bool {
-        !self.external_urls.is_empty()
+// defaults for users without a user_limits row, None is unlimited
+#[derive(Debug, Deserialize, Clone)]
+pub struct DealLimits {
+    #[serde(default = "DealLimits::default_concurrent")]
+    pub concurrent: i32,
+    pub daily: Option<i32>,
+    pub weekly: Option<i32>,
+    pub offer_cooldown_seconds: Option<i64>,
+}
+
+impl DealLimits {
+    fn default_concurrent() -> i32 {
+        5
+    }
+}
+
+impl Default for DealLimits {
+    fn default() -> Self {
+        Self {
+            concurrent: Self::default_concurrent(),
+            daily: None,
+            weekly: None,
+            offer_cooldown_seconds: None,
+        }
     }
 }
 
@@ -64,10 +171,20 @@ pub struct Settings {
     pub sensordata_api_base: String,
     pub recommendations_api_base: String,
     pub external_webhook_secret: String,
+    #[serde(default)]
     pub places_api_key: String,
     pub redis_connection_string: Option<String>,
     #[serde(default)]
     pub new_offer: NewOffer,
+    #[serde(default)]
+    pub menu_catalog: MenuCatalog,
+    #[serde(default)]
+    pub geocoder: Geocoder,
+    #[serde(default)]
+    pub deal_limits: DealLimits,
+    // push notifications are off without keys
+    pub web_push: Option<WebPush>,
+    pub discord_bot: Option<DiscordBot>,
 }
 
 impl Settings {
@@ -79,6 +196,11 @@ impl Settings {
                     .list_separator(",")
                     .with_list_parse_key("new_offer.discord_urls")
                     .with_list_parse_key("new_offer.external_urls")
+                    .with_list_parse_key("new_offer.slack_urls")
+                    .with_list_parse_key("new_offer.telegram.chat_ids")
+                    .with_list_parse_key("new_offer.ntfy.topics")
+                    .with_list_parse_key("new_offer.matrix.room_ids")
+                    .with_list_parse_key("menu_catalog.store_ids")
                     .try_parsing(true),
             )
             .build()?;
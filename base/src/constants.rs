@@ -7,6 +7,7 @@ pub mod mc_donalds {
     pub const LONGITUDE: &str = "-122.084";
     pub const LOCATION_SEARCH_DISTANCE: u64 = 500;
     pub const STORE_UNIQUE_ID_TYPE: &str = "NSN";
+    pub const COUNTRY_CODE: &str = "AU";
 }
 
 pub const IMAGE_BASE_URL: &str = "https://images.maccas.one";
@@ -1,56 +1,116 @@
 use crate::{constants, http::get_proxied_maccas_http_client};
+use anyhow::Context;
 use entity::accounts;
+use libmaccas::{RefreshLease, TokenLease, TokenStore, types::response::AccessTokenResponse};
 use reqwest::Proxy;
 use sea_orm::{
-    sea_query::{LockBehavior, LockType},
-    ActiveModelTrait, DatabaseTransaction, EntityTrait, IntoActiveModel, QuerySelect, Set,
-    TransactionTrait,
+    DatabaseConnection, DatabaseTransaction, EntityTrait, QuerySelect, Set, TransactionTrait,
+    Unchanged, prelude::Uuid, sea_query::LockType,
 };
 
+pub struct AccountTokenStore {
+    db: DatabaseConnection,
+    account_id: Uuid,
+}
+
+impl AccountTokenStore {
+    pub fn new(db: DatabaseConnection, account_id: Uuid) -> Self {
+        Self { db, account_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenStore for AccountTokenStore {
+    // the row lock is what stops two processes refreshing the same account, a client's
+    // own mutex only covers its clones
+    async fn lease(&self, refresh_token: &str) -> Result<RefreshLease, anyhow::Error> {
+        let txn = self.db.begin().await?;
+        let account = accounts::Entity::find_by_id(self.account_id)
+            .lock(LockType::Update)
+            .one(&txn)
+            .await?
+            .context("account not found")?;
+
+        // whoever held the lock before us refreshed and bumped refreshed_at, the
+        // rotated refresh token in the row is how we tell
+        if account.refresh_token != refresh_token {
+            tracing::info!("tokens were refreshed elsewhere, using those");
+            return Ok(RefreshLease::Refreshed(AccessTokenResponse {
+                access_token: account.access_token,
+                refresh_token: account.refresh_token,
+            }));
+        }
+
+        Ok(RefreshLease::Acquired(Box::new(AccountTokenLease {
+            txn,
+            account_id: self.account_id,
+        })))
+    }
+}
+
+struct AccountTokenLease {
+    txn: DatabaseTransaction,
+    account_id: Uuid,
+}
+
+#[async_trait::async_trait]
+impl TokenLease for AccountTokenLease {
+    async fn store(self: Box<Self>, tokens: &AccessTokenResponse) -> Result<(), anyhow::Error> {
+        tracing::info!("new tokens fetched, updating database");
+
+        // refreshed_at is bumped by the trigger on accounts
+        accounts::Entity::update(accounts::ActiveModel {
+            id: Unchanged(self.account_id),
+            access_token: Set(tokens.access_token.clone()),
+            refresh_token: Set(tokens.refresh_token.clone()),
+            ..Default::default()
+        })
+        .exec(&self.txn)
+        .await?;
+
+        self.txn.commit().await?;
+
+        Ok(())
+    }
+}
+
+pub fn get_maccas_api_client(
+    account: &accounts::Model,
+    http_client: reqwest_middleware::ClientWithMiddleware,
+    client_id: &str,
+    db: &DatabaseConnection,
+) -> libmaccas::ApiClient {
+    let mut api_client = libmaccas::ApiClient::new(
+        constants::mc_donalds::BASE_URL.to_owned(),
+        http_client,
+        client_id.to_owned(),
+    );
+
+    api_client.set_auth_token(&account.access_token);
+    api_client.set_refresh_token(&account.refresh_token);
+    // MUST USE CONNECTION OR WE'LL LOSE CREDENTIALS AND 401
+    api_client.set_token_store(AccountTokenStore::new(db.clone(), account.id));
+
+    api_client
+}
+
 pub async fn get_activated_maccas_api_client(
     account: accounts::Model,
     proxy: Proxy,
     client_id: &str,
-    db: &DatabaseTransaction,
+    db: &DatabaseConnection,
 ) -> Result<libmaccas::ApiClient, anyhow::Error> {
-    let mut api_client = libmaccas::ApiClient::new(
-        constants::mc_donalds::BASE_URL.to_owned(),
+    let api_client = get_maccas_api_client(
+        &account,
         get_proxied_maccas_http_client(proxy)?,
-        client_id.to_owned(),
+        client_id,
+        db,
     );
 
+    // refresh ahead of expiry to save a rejected request, the client handles the rest
     let now = chrono::Utc::now().naive_utc();
-
-    // refresh case
     if (now - account.refreshed_at).num_minutes() >= 14 {
-        let txn = db.begin().await?;
-        accounts::Entity::find_by_id(account.id)
-            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
-            .one(&txn)
-            .await?;
-
-        api_client.set_auth_token(&account.access_token);
-        let response = api_client
-            .customer_login_refresh(&account.refresh_token)
-            .await?;
-
-        let response = response
-            .body
-            .response
-            .ok_or_else(|| anyhow::Error::msg("access token refresh failed"))?;
-
-        api_client.set_auth_token(&response.access_token);
-
-        let mut update_model = account.into_active_model();
-
-        update_model.access_token = Set(response.access_token);
-        update_model.refresh_token = Set(response.refresh_token);
-        tracing::info!("new tokens fetched, updating database");
-
-        update_model.update(&txn).await?;
-        txn.commit().await?;
-    } else {
-        api_client.set_auth_token(&account.access_token);
+        api_client.refresh_tokens().await?;
     }
 
     Ok(api_client)
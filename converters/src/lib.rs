@@ -3,6 +3,8 @@ use thiserror::Error;
 
 pub mod offers;
 pub mod points;
+pub mod products;
+pub mod stores;
 pub struct Database<T>(pub T);
 
 #[derive(Error, Debug)]
@@ -0,0 +1,68 @@
+use crate::{ConversionError, Database};
+use entity::product_prices::Model as ProductPrices;
+use entity::products::Model as Products;
+use libmaccas::types::response::{Product, ProductPrice};
+
+impl Database<Products> {
+    pub fn convert_product(product: &Product) -> Result<Self, ConversionError> {
+        let now = chrono::offset::Utc::now().naive_utc();
+        let names = &product.names.names;
+        let product_name = names
+            .iter()
+            .find(|n| n.language_id == "en-AU")
+            .or(names.first())
+            .ok_or(ConversionError::Unknown)?;
+
+        let short_name = if product_name.short_name.is_empty() {
+            None
+        } else {
+            Some(product_name.short_name.clone())
+        };
+
+        Ok(Database(Products {
+            product_code: product.product_code,
+            name: product_name.long_name.clone(),
+            short_name,
+            image_name: product.display_image_name.clone(),
+            kilojoules: product.nutrition.as_ref().map(|n| n.energy),
+            nutrition: product
+                .nutrition
+                .as_ref()
+                .map(serde_json::to_value)
+                .transpose()?,
+            category_ids: product
+                .categories
+                .iter()
+                .flatten()
+                .map(|c| c.display_category_id)
+                .collect(),
+            menu_type_id: product.menu_type_id,
+            is_promotional: product.is_promotional,
+            is_salable: product.is_salable,
+            created_at: now,
+            updated_at: now,
+        }))
+    }
+}
+
+impl Database<ProductPrices> {
+    pub fn convert_product_prices(store_id: &str, product_price: &ProductPrice) -> Vec<Self> {
+        let now = chrono::offset::Utc::now().naive_utc();
+
+        product_price
+            .prices
+            .iter()
+            .filter(|p| p.is_valid)
+            .map(|p| {
+                Database(ProductPrices {
+                    store_id: store_id.to_owned(),
+                    product_code: product_price.product_code,
+                    price_type_id: p.price_type_id,
+                    price: p.price,
+                    created_at: now,
+                    updated_at: now,
+                })
+            })
+            .collect()
+    }
+}
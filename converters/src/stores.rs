@@ -0,0 +1,92 @@
+use crate::{ConversionError, Database};
+use entity::stores::Model as Stores;
+use libmaccas::types::response::{FullRestaurantInformation, Restaurant, WeekOpeningHour};
+
+// facility codes as returned on restaurant responses
+pub const DRIVE_THRU: &str = "DRIVETHRU";
+pub const MC_CAFE: &str = "MCCAFE";
+pub const OPEN_24H: &str = "TWENTYFOURHOURS";
+pub const TABLE_SERVICE: &str = "TABLESERVICE";
+pub const MOBILE_ORDER: &str = "MOBILEORDERS";
+
+fn has_facility(facilities: &[String], facility: &str) -> bool {
+    facilities.iter().any(|f| f.eq_ignore_ascii_case(facility))
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+fn opening_hours(
+    week_opening_hours: &[WeekOpeningHour],
+) -> Result<Option<serde_json::Value>, ConversionError> {
+    if week_opening_hours.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::to_value(week_opening_hours)?))
+}
+
+impl Database<Stores> {
+    pub fn convert_restaurant(
+        restaurant: &FullRestaurantInformation,
+    ) -> Result<Self, ConversionError> {
+        let now = chrono::offset::Utc::now().naive_utc();
+        let facilities = &restaurant.facilities;
+
+        // any point of distribution with digital services takes app orders
+        let mobile_order = has_facility(facilities, MOBILE_ORDER)
+            || restaurant
+                .catalog
+                .points_of_distribution
+                .iter()
+                .any(|p| !p.digital_services.is_empty());
+
+        Ok(Database(Stores {
+            id: restaurant.national_store_number.to_string(),
+            name: restaurant.name.clone(),
+            address: restaurant.address.address_line1.clone(),
+            created_at: now,
+            latitude: Some(restaurant.location.latitude),
+            longitude: Some(restaurant.location.longitude),
+            time_zone: Some(restaurant.time_zone.clone()),
+            opening_hours: opening_hours(&restaurant.week_opening_hours)?,
+            facilities: facilities.clone(),
+            drive_thru: has_facility(facilities, DRIVE_THRU),
+            mc_cafe: has_facility(facilities, MC_CAFE),
+            open_24h: has_facility(facilities, OPEN_24H),
+            table_service: has_facility(facilities, TABLE_SERVICE)
+                || restaurant.catalog.table_service.enable_postable_service,
+            mobile_order,
+            updated_at: now,
+            suburb: non_empty(&restaurant.address.city_town),
+            postcode: restaurant.address.postal_zip.as_deref().and_then(non_empty),
+        }))
+    }
+
+    pub fn convert_restaurant_summary(restaurant: &Restaurant) -> Result<Self, ConversionError> {
+        let now = chrono::offset::Utc::now().naive_utc();
+        let facilities = &restaurant.facilities;
+
+        Ok(Database(Stores {
+            id: restaurant.national_store_number.to_string(),
+            name: restaurant.name.clone(),
+            address: restaurant.address.address_line1.clone(),
+            created_at: now,
+            latitude: Some(restaurant.location.latitude),
+            longitude: Some(restaurant.location.longitude),
+            time_zone: restaurant.time_zone.clone(),
+            opening_hours: opening_hours(&restaurant.week_opening_hours)?,
+            facilities: facilities.clone(),
+            drive_thru: has_facility(facilities, DRIVE_THRU),
+            mc_cafe: has_facility(facilities, MC_CAFE),
+            open_24h: has_facility(facilities, OPEN_24H),
+            table_service: has_facility(facilities, TABLE_SERVICE),
+            mobile_order: has_facility(facilities, MOBILE_ORDER),
+            updated_at: now,
+            suburb: non_empty(&restaurant.address.city_town),
+            postcode: restaurant.address.postal_zip.as_deref().and_then(non_empty),
+        }))
+    }
+}
@@ -25,6 +25,7 @@ pub struct Model {
     pub status: EventStatus,
     pub trace_id: Option<String>,
     pub hash: String,
+    pub message_id: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -4,19 +4,31 @@ pub mod prelude;
 
 pub mod account_lock;
 pub mod accounts;
+pub mod active_transactions;
 pub mod categories;
 pub mod concurrent_active_deals;
+pub mod discord_users;
 pub mod events;
 pub mod job_history;
 pub mod jobs;
+pub mod notification_deliveries;
+pub mod notification_rules;
 pub mod offer_audit;
 pub mod offer_cluster_score;
 pub mod offer_details;
 pub mod offer_embeddings;
+pub mod offer_favourites;
 pub mod offer_history;
 pub mod offer_name_cluster_association;
+pub mod offer_waitlist;
 pub mod offers;
 pub mod points;
+pub mod product_prices;
+pub mod products;
+pub mod push_subscriptions;
 pub mod recommendations;
 pub mod sea_orm_active_enums;
+pub mod store_search_areas;
 pub mod stores;
+pub mod user_deal_rollup;
+pub mod user_limits;
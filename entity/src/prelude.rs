@@ -2,18 +2,30 @@
 
 pub use super::account_lock::Entity as AccountLock;
 pub use super::accounts::Entity as Accounts;
+pub use super::active_transactions::Entity as ActiveTransactions;
 pub use super::categories::Entity as Categories;
 pub use super::concurrent_active_deals::Entity as ConcurrentActiveDeals;
+pub use super::discord_users::Entity as DiscordUsers;
 pub use super::events::Entity as Events;
 pub use super::job_history::Entity as JobHistory;
 pub use super::jobs::Entity as Jobs;
+pub use super::notification_deliveries::Entity as NotificationDeliveries;
+pub use super::notification_rules::Entity as NotificationRules;
 pub use super::offer_audit::Entity as OfferAudit;
 pub use super::offer_cluster_score::Entity as OfferClusterScore;
 pub use super::offer_details::Entity as OfferDetails;
 pub use super::offer_embeddings::Entity as OfferEmbeddings;
+pub use super::offer_favourites::Entity as OfferFavourites;
 pub use super::offer_history::Entity as OfferHistory;
 pub use super::offer_name_cluster_association::Entity as OfferNameClusterAssociation;
+pub use super::offer_waitlist::Entity as OfferWaitlist;
 pub use super::offers::Entity as Offers;
 pub use super::points::Entity as Points;
+pub use super::product_prices::Entity as ProductPrices;
+pub use super::products::Entity as Products;
+pub use super::push_subscriptions::Entity as PushSubscriptions;
 pub use super::recommendations::Entity as Recommendations;
+pub use super::store_search_areas::Entity as StoreSearchAreas;
 pub use super::stores::Entity as Stores;
+pub use super::user_deal_rollup::Entity as UserDealRollup;
+pub use super::user_limits::Entity as UserLimits;
@@ -10,6 +10,16 @@ pub enum Action {
     Add,
     #[sea_orm(string_value = "remove")]
     Remove,
+    #[sea_orm(string_value = "extend")]
+    Extend,
+}
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "delivery_status")]
+pub enum DeliveryStatus {
+    #[sea_orm(string_value = "delivered")]
+    Delivered,
+    #[sea_orm(string_value = "failed")]
+    Failed,
 }
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "event_status")]
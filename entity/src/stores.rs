@@ -3,7 +3,7 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "stores")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
@@ -11,6 +11,22 @@ pub struct Model {
     pub name: String,
     pub address: String,
     pub created_at: DateTime,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub latitude: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub longitude: Option<f64>,
+    pub time_zone: Option<String>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub opening_hours: Option<Json>,
+    pub facilities: Vec<String>,
+    pub drive_thru: bool,
+    pub mc_cafe: bool,
+    pub open_24h: bool,
+    pub table_service: bool,
+    pub mobile_order: bool,
+    pub updated_at: DateTime,
+    pub suburb: Option<String>,
+    pub postcode: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,17 +1,20 @@
+use crate::token_store::{RefreshLease, TokenStore};
 use crate::types::request::{
     ActivateAndSignInRequest, ActivationRequest, EmailRequest, RegistrationRequest,
 };
 use crate::types::response::{
-    ActivateAndSignInResponse, ActivationResponse, CatalogResponse, CategoriesResponse,
-    ClientResponse, CustomerPointResponse, EmailResponse, LoginRefreshResponse, LoginResponse,
-    OfferDealStackResponse, OfferDetailsResponse, OfferResponse, RegistrationResponse,
-    RestaurantLocationResponse, RestaurantResponse, TokenResponse,
+    AccessTokenResponse, ActivateAndSignInResponse, ActivationResponse, CatalogResponse,
+    CategoriesResponse, ClientResponse, CustomerPointResponse, EmailResponse, LoginRefreshResponse,
+    LoginResponse, OfferDealStackResponse, OfferDetailsResponse, OfferResponse,
+    RegistrationResponse, RestaurantLocationResponse, RestaurantResponse, TokenResponse,
 };
-use crate::ClientResult;
+use crate::{ClientError, ClientResult};
 use anyhow::Context;
+use http::StatusCode;
 use http::method::Method;
 use reqwest_middleware::{ClientWithMiddleware, RequestBuilder};
 use std::fmt::{Debug, Display};
+use std::sync::{Arc, RwLock};
 use tracing::instrument;
 use uuid::Uuid;
 
@@ -19,11 +22,20 @@ use uuid::Uuid;
 pub struct ApiClient {
     base_url: String,
     client: ClientWithMiddleware,
-    auth_token: Option<String>,
+    // shared between clones so a refresh is seen by all of them
+    tokens: Arc<RwLock<Tokens>>,
+    refresh_lock: Arc<tokio::sync::Mutex<()>>,
+    token_store: Option<Arc<dyn TokenStore>>,
     login_token: Option<String>,
     client_id: String,
 }
 
+#[derive(Default)]
+struct Tokens {
+    auth_token: Option<String>,
+    refresh_token: Option<String>,
+}
+
 impl Debug for ApiClient {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ApiClient")
@@ -39,7 +51,9 @@ impl ApiClient {
             base_url,
             client,
             login_token: None,
-            auth_token: None,
+            tokens: Default::default(),
+            refresh_lock: Default::default(),
+            token_store: None,
             client_id,
         }
     }
@@ -79,7 +93,115 @@ impl ApiClient {
     where
         S: Display + ?Sized + Debug,
     {
-        self.auth_token = Some(auth_token.to_string());
+        self.tokens_mut().auth_token = Some(auth_token.to_string());
+    }
+
+    pub fn set_refresh_token<S>(&mut self, refresh_token: &S)
+    where
+        S: Display + ?Sized + Debug,
+    {
+        self.tokens_mut().refresh_token = Some(refresh_token.to_string());
+    }
+
+    pub fn set_token_store<T>(&mut self, token_store: T)
+    where
+        T: TokenStore + 'static,
+    {
+        self.token_store = Some(Arc::new(token_store));
+    }
+
+    fn tokens(&self) -> std::sync::RwLockReadGuard<'_, Tokens> {
+        self.tokens.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn tokens_mut(&self) -> std::sync::RwLockWriteGuard<'_, Tokens> {
+        self.tokens.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn auth_token(&self) -> ClientResult<String> {
+        Ok(self
+            .tokens()
+            .auth_token
+            .clone()
+            .context("no auth token set")?)
+    }
+
+    // exchanges the refresh token for a new pair and persists it through the token store
+    pub async fn refresh_tokens(&self) -> ClientResult<AccessTokenResponse> {
+        let _guard = self.refresh_lock.lock().await;
+        self.refresh_tokens_locked().await
+    }
+
+    async fn refresh_tokens_locked(&self) -> ClientResult<AccessTokenResponse> {
+        let refresh_token = self
+            .tokens()
+            .refresh_token
+            .clone()
+            .context("no refresh token set")?;
+
+        let lease = match &self.token_store {
+            Some(token_store) => match token_store.lease(&refresh_token).await? {
+                RefreshLease::Refreshed(tokens) => {
+                    self.set_tokens(&tokens);
+                    return Ok(tokens);
+                }
+                RefreshLease::Acquired(lease) => Some(lease),
+            },
+            None => None,
+        };
+
+        let tokens = self
+            .customer_login_refresh(&refresh_token)
+            .await?
+            .body
+            .response
+            .ok_or(ClientError::Unauthorized)?;
+
+        // the old refresh token is already revoked, if these can't be stored nothing else
+        // can use them either so the failure is surfaced instead
+        if let Some(lease) = lease {
+            lease.store(&tokens).await?;
+        }
+
+        self.set_tokens(&tokens);
+        Ok(tokens)
+    }
+
+    fn set_tokens(&self, tokens: &AccessTokenResponse) {
+        let mut current = self.tokens_mut();
+        current.auth_token = Some(tokens.access_token.clone());
+        current.refresh_token = Some(tokens.refresh_token.clone());
+    }
+
+    // on a 401 the tokens are refreshed and the request is retried once,
+    // only when there is a token store to keep the new tokens in
+    async fn send_authenticated<T, F>(&self, request: F) -> ClientResult<ClientResponse<T>>
+    where
+        T: for<'de> serde::Deserialize<'de> + Debug,
+        F: Fn(&str) -> RequestBuilder,
+    {
+        let token = self.auth_token()?;
+        let response = request(&token).send().await?;
+        tracing::debug!("raw response: {:?}", response);
+
+        if response.status() != StatusCode::UNAUTHORIZED || self.token_store.is_none() {
+            return ClientResponse::from_response(response).await;
+        }
+
+        tracing::warn!("access token rejected, refreshing and retrying");
+        let token = {
+            let _guard = self.refresh_lock.lock().await;
+            // another request may have refreshed while we waited
+            match self.auth_token()? {
+                current if current != token => current,
+                _ => self.refresh_tokens_locked().await?.access_token,
+            }
+        };
+
+        let response = request(&token).send().await?;
+        tracing::debug!("raw response: {:?}", response);
+
+        ClientResponse::from_response(response).await
     }
 
     // POST https://ap-prod.api.mcd.com/v1/security/auth/token
@@ -294,16 +416,12 @@ impl ApiClient {
             ),
         ]);
 
-        let token = self.auth_token.as_ref().context("no auth token set")?;
-        let request = self
-            .get_default_request("exp/v1/offers", Method::GET)
-            .query(&params)
-            .bearer_auth(token);
-
-        let response = request.send().await?;
-        tracing::debug!("raw response: {:?}", response);
-
-        ClientResponse::from_response(response).await
+        self.send_authenticated(|token| {
+            self.get_default_request("exp/v1/offers", Method::GET)
+                .query(&params)
+                .bearer_auth(token)
+        })
+        .await
     }
 
     // GET https://ap-prod.api.mcd.com/exp/v1/restaurant/location?distance=20&filter=summary&latitude=-32.0117&longitude=115.8845
@@ -328,16 +446,12 @@ impl ApiClient {
             (String::from("filter"), filter.to_string()),
         ]);
 
-        let token = self.auth_token.as_ref().context("no auth token set")?;
-        let request = self
-            .get_default_request("exp/v1/restaurant/location", Method::GET)
-            .query(&params)
-            .bearer_auth(token);
-
-        let response = request.send().await?;
-        tracing::debug!("raw response: {:?}", response);
-
-        ClientResponse::from_response(response).await
+        self.send_authenticated(|token| {
+            self.get_default_request("exp/v1/restaurant/location", Method::GET)
+                .query(&params)
+                .bearer_auth(token)
+        })
+        .await
     }
 
     // GET https://ap-prod.api.mcd.com/exp/v1/offers/details/166870
@@ -349,19 +463,14 @@ impl ApiClient {
     where
         S: Display + ?Sized + Debug,
     {
-        let token = self.auth_token.as_ref().context("no auth token set")?;
-
-        let request = self
-            .get_default_request(
+        self.send_authenticated(|token| {
+            self.get_default_request(
                 format!("exp/v1/offers/details/{offer_proposition_id}").as_str(),
                 Method::GET,
             )
-            .bearer_auth(token);
-
-        let response = request.send().await?;
-        tracing::debug!("raw response: {:?}", response);
-
-        ClientResponse::from_response(response).await
+            .bearer_auth(token)
+        })
+        .await
     }
 
     // GET https://ap-prod.api.mcd.com/exp/v1/offers/dealstack?offset=480&storeId=951488
@@ -375,21 +484,17 @@ impl ApiClient {
         A: Display + ?Sized + Debug,
         B: Display + ?Sized + Debug,
     {
-        let token = self.auth_token.as_ref().context("no auth token set")?;
         let params = Vec::from([
             (String::from("offset"), offset.to_string()),
             (String::from("storeId"), store_id.to_string()),
         ]);
 
-        let request = self
-            .get_default_request("exp/v1/offers/dealstack", Method::GET)
-            .query(&params)
-            .bearer_auth(token);
-
-        let response = request.send().await?;
-        tracing::debug!("raw response: {:?}", response);
-
-        ClientResponse::from_response(response).await
+        self.send_authenticated(|token| {
+            self.get_default_request("exp/v1/offers/dealstack", Method::GET)
+                .query(&params)
+                .bearer_auth(token)
+        })
+        .await
     }
 
     // POST https://ap-prod.api.mcd.com/exp/v1/offers/dealstack/166870?offerId=1139347703&offset=480&storeId=951488
@@ -405,24 +510,20 @@ impl ApiClient {
         B: Display + ?Sized + Debug,
         C: Display + ?Sized + Debug,
     {
-        let token = self.auth_token.as_ref().context("no auth token set")?;
         let params = Vec::from([
             (String::from("offset"), offset.to_string()),
             (String::from("storeId"), store_id.to_string()),
         ]);
 
-        let request = self
-            .get_default_request(
+        self.send_authenticated(|token| {
+            self.get_default_request(
                 format!("exp/v1/offers/dealstack/{offer_id}").as_str(),
                 Method::POST,
             )
             .query(&params)
-            .bearer_auth(token);
-
-        let response = request.send().await?;
-        tracing::debug!("raw response: {:?}", response);
-
-        ClientResponse::from_response(response).await
+            .bearer_auth(token)
+        })
+        .await
     }
 
     // DELETE https://ap-prod.api.mcd.com/exp/v1/offers/dealstack/offer/166870?offerId=1139347703&offset=480&storeId=951488
@@ -450,40 +551,32 @@ impl ApiClient {
             }
         );
 
-        let token = self.auth_token.as_ref().context("no auth token set")?;
         let params = Vec::from([
             (String::from("offerId"), offer_id.to_string()),
             (String::from("offset"), offset.to_string()),
             (String::from("storeId"), store_id.to_string()),
         ]);
 
-        let request = self
-            .get_default_request(
+        self.send_authenticated(|token| {
+            self.get_default_request(
                 format!("exp/v1/offers/dealstack/offer/{offer_proposition_id}").as_str(),
                 Method::DELETE,
             )
             .json(&body)
             .query(&params)
-            .bearer_auth(token);
-
-        let response = request.send().await?;
-        tracing::debug!("raw response: {:?}", response);
-
-        ClientResponse::from_response(response).await
+            .bearer_auth(token)
+        })
+        .await
     }
 
     // DELETE https://ap-prod.api.mcd.com/exp/v1/offers/dealstack
     #[instrument(skip(self), fields(statusCode))]
     pub async fn clear_dealstack(&self) -> ClientResult<ClientResponse<OfferDealStackResponse>> {
-        let token = self.auth_token.as_ref().context("no auth token set")?;
-        let request = self
-            .get_default_request("exp/v1/offers/dealstack/offer", Method::DELETE)
-            .bearer_auth(token);
-
-        let response = request.send().await?;
-        tracing::debug!("raw response: {:?}", response);
-
-        ClientResponse::from_response(response).await
+        self.send_authenticated(|token| {
+            self.get_default_request("exp/v1/offers/dealstack/offer", Method::DELETE)
+                .bearer_auth(token)
+        })
+        .await
     }
 
     // POST https://ap-prod.api.mcd.com/exp/v1/customer/login/refresh
@@ -495,7 +588,8 @@ impl ApiClient {
     where
         S: Display + ?Sized + Debug,
     {
-        let token = self.auth_token.as_ref().context("no auth token set")?;
+        // not retried on 401, a rejected refresh has nothing to fall back to
+        let token = self.auth_token()?;
         let body = serde_json::json!({ "refreshToken": refresh_token.to_string() });
 
         let request = self
@@ -512,15 +606,11 @@ impl ApiClient {
     // GET https://ap-prod.api.mcd.com/exp/v1/loyalty/customer/points
     #[instrument(skip(self), fields(statusCode))]
     pub async fn get_customer_points(&self) -> ClientResult<ClientResponse<CustomerPointResponse>> {
-        let token = self.auth_token.as_ref().context("no auth token set")?;
-        let request = self
-            .get_default_request("exp/v1/loyalty/customer/points", Method::GET)
-            .bearer_auth(token);
-
-        let response = request.send().await?;
-        tracing::debug!("raw response: {:?}", response);
-
-        ClientResponse::from_response(response).await
+        self.send_authenticated(|token| {
+            self.get_default_request("exp/v1/loyalty/customer/points", Method::GET)
+                .bearer_auth(token)
+        })
+        .await
     }
 
     // GET https://ap-prod.api.mcd.com/exp/v1/menu/catalog/AU/950442?filter=summary
@@ -536,20 +626,16 @@ impl ApiClient {
         B: Display + ?Sized + Debug,
         C: Display + ?Sized + Debug,
     {
-        let token = self.auth_token.as_ref().context("no auth token set")?;
         let params = Vec::from([(String::from("filter"), filter.to_string())]);
-        let request = self
-            .get_default_request(
+        self.send_authenticated(|token| {
+            self.get_default_request(
                 format!("exp/v1/menu/catalog/{}/{}", country_code, store_id).as_str(),
                 Method::GET,
             )
             .query(&params)
-            .bearer_auth(token);
-
-        let response = request.send().await?;
-        tracing::debug!("raw response: {:?}", response);
-
-        ClientResponse::from_response(response).await
+            .bearer_auth(token)
+        })
+        .await
     }
 
     // GET https://ap-prod.api.mcd.com/exp/v1/menu/1/category
@@ -561,15 +647,11 @@ impl ApiClient {
     where
         A: Display + ?Sized + Debug,
     {
-        let token = self.auth_token.as_ref().context("no auth token set")?;
-        let request = self
-            .get_default_request(format!("exp/v1/menu/{}/category", id).as_str(), Method::GET)
-            .bearer_auth(token);
-
-        let response = request.send().await?;
-        tracing::debug!("raw response: {:?}", response);
-
-        ClientResponse::from_response(response).await
+        self.send_authenticated(|token| {
+            self.get_default_request(format!("exp/v1/menu/{}/category", id).as_str(), Method::GET)
+                .bearer_auth(token)
+        })
+        .await
     }
 
     // GET https://ap-prod.api.mcd.com/exp/v1/restaurant/951094?filter=full&storeUniqueIdType=NSN
@@ -585,7 +667,6 @@ impl ApiClient {
         B: Display + ?Sized + Debug,
         C: Display + ?Sized + Debug,
     {
-        let token = self.auth_token.as_ref().context("no auth token set")?;
         let params = Vec::from([
             (String::from("filter"), filter.to_string()),
             (
@@ -593,17 +674,14 @@ impl ApiClient {
                 store_unique_id_type.to_string(),
             ),
         ]);
-        let request = self
-            .get_default_request(
+        self.send_authenticated(|token| {
+            self.get_default_request(
                 format!("exp/v1/restaurant/{}", store_id).as_str(),
                 Method::GET,
             )
             .query(&params)
-            .bearer_auth(token);
-
-        let response = request.send().await?;
-        tracing::debug!("raw response: {:?}", response);
-
-        ClientResponse::from_response(response).await
+            .bearer_auth(token)
+        })
+        .await
     }
 }
@@ -1,10 +1,24 @@
 use http::StatusCode;
-use std::{error::Error, fmt::Display, num::ParseIntError};
+use std::{error::Error, fmt::Display, num::ParseIntError, time::Duration};
 
 #[derive(Debug)]
 pub enum ClientError {
     RequestOrMiddlewareError(reqwest_middleware::Error),
     RequestError(reqwest::Error),
+    // 401, the access or refresh token was rejected
+    Unauthorized,
+    // 403 that survived AkamaiCdnRetryStrategy, bot detection or the CDN blocked us
+    Blocked,
+    RateLimited {
+        retry_after: Option<Duration>,
+    },
+    // 423, the account itself is locked upstream
+    AccountLocked,
+    SchemaMismatch {
+        source: serde_json::Error,
+        body: String,
+    },
+    Upstream(StatusCode),
     Other(anyhow::Error),
 }
 
@@ -40,9 +54,48 @@ impl ClientError {
                 reqwest_middleware::Error::Reqwest(e) => e.status(),
             },
             ClientError::RequestError(e) => e.status(),
+            ClientError::Unauthorized => Some(StatusCode::UNAUTHORIZED),
+            ClientError::Blocked => Some(StatusCode::FORBIDDEN),
+            ClientError::RateLimited { .. } => Some(StatusCode::TOO_MANY_REQUESTS),
+            ClientError::AccountLocked => Some(StatusCode::LOCKED),
+            ClientError::SchemaMismatch { .. } => None,
+            ClientError::Upstream(status) => Some(*status),
             ClientError::Other(_) => None,
         }
     }
+
+    pub fn is_auth_failure(&self) -> bool {
+        matches!(self, ClientError::Unauthorized)
+    }
+
+    // failures that say nothing about the account, retrying later may succeed
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ClientError::Blocked
+                | ClientError::RateLimited { .. }
+                | ClientError::Upstream(_)
+                | ClientError::RequestOrMiddlewareError(_)
+                | ClientError::RequestError(_)
+        )
+    }
+
+    pub(crate) fn from_status(status: StatusCode, headers: &http::HeaderMap) -> Option<Self> {
+        match status {
+            StatusCode::UNAUTHORIZED => Some(ClientError::Unauthorized),
+            StatusCode::FORBIDDEN => Some(ClientError::Blocked),
+            StatusCode::LOCKED => Some(ClientError::AccountLocked),
+            StatusCode::TOO_MANY_REQUESTS => Some(ClientError::RateLimited {
+                retry_after: headers
+                    .get(http::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse::<u64>().ok())
+                    .map(Duration::from_secs),
+            }),
+            status if status.is_server_error() => Some(ClientError::Upstream(status)),
+            _ => None,
+        }
+    }
 }
 
 impl Error for ClientError {
@@ -50,7 +103,13 @@ impl Error for ClientError {
         match self {
             ClientError::RequestOrMiddlewareError(e) => e.source(),
             ClientError::RequestError(e) => e.source(),
+            ClientError::SchemaMismatch { source, .. } => Some(source),
             ClientError::Other(e) => e.source(),
+            ClientError::Unauthorized
+            | ClientError::Blocked
+            | ClientError::RateLimited { .. }
+            | ClientError::AccountLocked
+            | ClientError::Upstream(_) => None,
         }
     }
 
@@ -68,6 +127,17 @@ impl Display for ClientError {
         match self {
             ClientError::RequestOrMiddlewareError(e) => e.fmt(f),
             ClientError::RequestError(e) => e.fmt(f),
+            ClientError::Unauthorized => write!(f, "unauthorized, token rejected"),
+            ClientError::Blocked => write!(f, "request blocked by bot detection"),
+            ClientError::RateLimited { retry_after } => match retry_after {
+                Some(d) => write!(f, "rate limited, retry after {}s", d.as_secs()),
+                None => write!(f, "rate limited"),
+            },
+            ClientError::AccountLocked => write!(f, "account is locked"),
+            ClientError::SchemaMismatch { source, body } => {
+                write!(f, "unexpected response body: {source}, body: {body}")
+            }
+            ClientError::Upstream(status) => write!(f, "upstream error: {status}"),
             ClientError::Other(e) => e.fmt(f),
         }
     }
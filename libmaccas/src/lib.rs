@@ -1,9 +1,13 @@
 mod api;
 mod error;
+#[cfg(feature = "test-server")]
+pub mod testing;
+mod token_store;
 pub mod types;
 
 pub type ClientResult<T> = Result<T, ClientError>;
 
 pub use api::ApiClient;
 pub use error::ClientError;
+pub use token_store::{RefreshLease, TokenLease, TokenStore};
 pub use types::response::ClientResponse;
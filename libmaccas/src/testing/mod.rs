@@ -0,0 +1,277 @@
+use crate::ApiClient;
+use axum::{
+    Router,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, Method, Uri},
+    response::{IntoResponse, Response},
+};
+use http::StatusCode;
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use tokio::{net::TcpListener, task::JoinHandle};
+
+mod responses;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    SecurityAuthToken,
+    CustomerLoginRefresh,
+    Offers,
+    OffersDealStack,
+    AddToOffersDealStack,
+    RemoveFromOffersDealStack,
+    ClearDealStack,
+    CustomerPoints,
+    RestaurantLocation,
+}
+
+impl Endpoint {
+    fn from_request(method: &Method, path: &str) -> Option<Self> {
+        let path = path.trim_start_matches('/');
+
+        match (method, path) {
+            (&Method::POST, "v1/security/auth/token") => Some(Self::SecurityAuthToken),
+            (&Method::POST, "exp/v1/customer/login/refresh") => Some(Self::CustomerLoginRefresh),
+            (&Method::GET, "exp/v1/offers") => Some(Self::Offers),
+            (&Method::GET, "exp/v1/offers/dealstack") => Some(Self::OffersDealStack),
+            (&Method::DELETE, "exp/v1/offers/dealstack/offer") => Some(Self::ClearDealStack),
+            (&Method::DELETE, p) if p.starts_with("exp/v1/offers/dealstack/offer/") => {
+                Some(Self::RemoveFromOffersDealStack)
+            }
+            (&Method::POST, p) if p.starts_with("exp/v1/offers/dealstack/") => {
+                Some(Self::AddToOffersDealStack)
+            }
+            (&Method::GET, "exp/v1/loyalty/customer/points") => Some(Self::CustomerPoints),
+            (&Method::GET, "exp/v1/restaurant/location") => Some(Self::RestaurantLocation),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    // what the edge returns when bot detection rejects the request
+    AkamaiBlock,
+    Locked,
+    Unauthorized,
+    TooManyRequests,
+    ServerError,
+    MalformedJson,
+}
+
+impl Fault {
+    fn into_response(self) -> MockResponse {
+        match self {
+            Fault::AkamaiBlock => MockResponse::raw(
+                StatusCode::FORBIDDEN,
+                "<HTML><HEAD><TITLE>Access Denied</TITLE></HEAD><BODY>Access Denied</BODY></HTML>",
+            ),
+            Fault::Locked => MockResponse::status(StatusCode::LOCKED),
+            Fault::Unauthorized => MockResponse::status(StatusCode::UNAUTHORIZED),
+            Fault::TooManyRequests => MockResponse::status(StatusCode::TOO_MANY_REQUESTS),
+            Fault::ServerError => MockResponse::status(StatusCode::INTERNAL_SERVER_ERROR),
+            Fault::MalformedJson => MockResponse::raw(StatusCode::OK, r#"{"status":{"code":2"#),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum MockBody {
+    Json(Value),
+    Raw(String),
+    Empty,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: StatusCode,
+    body: MockBody,
+}
+
+impl MockResponse {
+    pub fn json<T: Serialize>(body: &T) -> Self {
+        Self {
+            status: StatusCode::OK,
+            body: MockBody::Json(serde_json::to_value(body).unwrap_or_default()),
+        }
+    }
+
+    pub fn raw(status: StatusCode, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: MockBody::Raw(body.into()),
+        }
+    }
+
+    pub fn status(status: StatusCode) -> Self {
+        Self {
+            status,
+            body: MockBody::Empty,
+        }
+    }
+
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+}
+
+impl IntoResponse for MockResponse {
+    fn into_response(self) -> Response {
+        match self.body {
+            MockBody::Json(v) => (self.status, axum::Json(v)).into_response(),
+            MockBody::Raw(s) => (
+                self.status,
+                [(http::header::CONTENT_TYPE, "application/json")],
+                s,
+            )
+                .into_response(),
+            MockBody::Empty => self.status.into_response(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub endpoint: Endpoint,
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+#[derive(Default)]
+struct MockState {
+    // scripted responses are consumed in order, then the default is used
+    scripted: HashMap<Endpoint, VecDeque<MockResponse>>,
+    defaults: HashMap<Endpoint, MockResponse>,
+    requests: Vec<RecordedRequest>,
+}
+
+pub struct MockServer {
+    address: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockServer {
+    pub async fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?;
+
+        let state = Arc::new(Mutex::new(MockState {
+            defaults: responses::defaults(),
+            ..Default::default()
+        }));
+
+        let app = Router::new().fallback(handle).with_state(state.clone());
+        let handle = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                tracing::error!("mock server stopped: {e}");
+            }
+        });
+
+        Ok(Self {
+            address,
+            state,
+            handle,
+        })
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    pub fn api_client(&self, client_id: &str) -> ApiClient {
+        let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new()).build();
+        ApiClient::new(self.base_url(), client, client_id.to_owned())
+    }
+
+    pub fn set_default(&self, endpoint: Endpoint, response: MockResponse) {
+        self.lock().defaults.insert(endpoint, response);
+    }
+
+    pub fn respond_with(&self, endpoint: Endpoint, response: MockResponse) {
+        self.lock()
+            .scripted
+            .entry(endpoint)
+            .or_default()
+            .push_back(response);
+    }
+
+    pub fn fail_with(&self, endpoint: Endpoint, fault: Fault, times: usize) {
+        let mut state = self.lock();
+        let queue = state.scripted.entry(endpoint).or_default();
+        for _ in 0..times {
+            queue.push_back(fault.into_response());
+        }
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.lock().requests.clone()
+    }
+
+    pub fn requests_for(&self, endpoint: Endpoint) -> Vec<RecordedRequest> {
+        self.lock()
+            .requests
+            .iter()
+            .filter(|r| r.endpoint == endpoint)
+            .cloned()
+            .collect()
+    }
+
+    pub fn reset(&self) {
+        let mut state = self.lock();
+        state.scripted.clear();
+        state.requests.clear();
+        state.defaults = responses::defaults();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn handle(
+    State(state): State<Arc<Mutex<MockState>>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let Some(endpoint) = Endpoint::from_request(&method, uri.path()) else {
+        tracing::warn!("mock server has no handler for {method} {uri}");
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+    state.requests.push(RecordedRequest {
+        endpoint,
+        method,
+        uri,
+        headers,
+        body,
+    });
+
+    let scripted = state
+        .scripted
+        .get_mut(&endpoint)
+        .and_then(|queue| queue.pop_front());
+
+    match scripted.or_else(|| state.defaults.get(&endpoint).cloned()) {
+        Some(response) => response.into_response(),
+        None => StatusCode::NOT_IMPLEMENTED.into_response(),
+    }
+}
@@ -0,0 +1,119 @@
+use super::{Endpoint, MockResponse};
+use crate::types::response::{
+    AccessTokenResponse, Address, CustomerPointResponse, DealStack, Location, LoginRefreshResponse,
+    OfferDealStack, OfferDealStackResponse, OfferList, OfferResponse, PointInformationResponse,
+    Restaurant, RestaurantLocationList, RestaurantLocationResponse, Status,
+};
+use std::collections::HashMap;
+
+fn ok_status() -> Status {
+    Status {
+        code: 20000.into(),
+        type_field: Some("Success".to_owned()),
+        correlation_id: None,
+        message: Some("Request Successful".to_owned()),
+    }
+}
+
+pub(super) fn deal_stack() -> OfferDealStack {
+    OfferDealStack {
+        random_code: "TEST".to_owned(),
+        bar_code_content: "TEST".to_owned(),
+        expiration_time: "2099-01-01T00:00:00Z".to_owned(),
+        deal_stack: Some(vec![]),
+    }
+}
+
+pub(super) fn defaults() -> HashMap<Endpoint, MockResponse> {
+    HashMap::from([
+        (
+            Endpoint::SecurityAuthToken,
+            MockResponse::json(&serde_json::json!({
+                "status": ok_status(),
+                "response": { "token": "mock-login-token", "expires": 900 }
+            })),
+        ),
+        (
+            Endpoint::CustomerLoginRefresh,
+            MockResponse::json(&LoginRefreshResponse {
+                status: ok_status(),
+                response: Some(AccessTokenResponse {
+                    access_token: "mock-access-token".to_owned(),
+                    refresh_token: "mock-refresh-token".to_owned(),
+                }),
+            }),
+        ),
+        (
+            Endpoint::Offers,
+            MockResponse::json(&OfferResponse {
+                status: ok_status(),
+                response: Some(OfferList { offers: vec![] }),
+            }),
+        ),
+        (
+            Endpoint::OffersDealStack,
+            MockResponse::json(&OfferDealStackResponse {
+                status: ok_status(),
+                response: Some(deal_stack()),
+            }),
+        ),
+        (
+            Endpoint::AddToOffersDealStack,
+            MockResponse::json(&OfferDealStackResponse {
+                status: ok_status(),
+                response: Some(OfferDealStack {
+                    deal_stack: Some(vec![DealStack::default()]),
+                    ..deal_stack()
+                }),
+            }),
+        ),
+        (
+            Endpoint::RemoveFromOffersDealStack,
+            MockResponse::json(&OfferDealStackResponse {
+                status: ok_status(),
+                response: Some(deal_stack()),
+            }),
+        ),
+        (
+            Endpoint::ClearDealStack,
+            MockResponse::json(&OfferDealStackResponse {
+                status: ok_status(),
+                response: Some(deal_stack()),
+            }),
+        ),
+        (
+            Endpoint::CustomerPoints,
+            MockResponse::json(&CustomerPointResponse {
+                status: ok_status(),
+                response: PointInformationResponse {
+                    total_points: 0,
+                    life_time_points: 0,
+                },
+            }),
+        ),
+        (
+            Endpoint::RestaurantLocation,
+            MockResponse::json(&RestaurantLocationResponse {
+                status: ok_status(),
+                response: Some(RestaurantLocationList {
+                    restaurants: vec![Restaurant {
+                        restaurant_status: "OPEN".to_owned(),
+                        address: Address {
+                            address_line1: "1 Test Street".to_owned(),
+                            city_town: "Perth".to_owned(),
+                            country: "AU".to_owned(),
+                            postal_zip: Some("6000".to_owned()),
+                        },
+                        location: Location {
+                            latitude: -31.9523,
+                            longitude: 115.8613,
+                        },
+                        name: "Test Store".to_owned(),
+                        national_store_number: 951488,
+                        ..Default::default()
+                    }],
+                }),
+            }),
+        ),
+    ])
+}
@@ -0,0 +1,20 @@
+use crate::types::response::AccessTokenResponse;
+
+pub enum RefreshLease {
+    // someone else refreshed since our tokens were loaded, these are theirs
+    Refreshed(AccessTokenResponse),
+    // ours to refresh, whatever lock the store took is held until the lease is stored or dropped
+    Acquired(Box<dyn TokenLease>),
+}
+
+// somewhere to persist tokens after ApiClient refreshes them itself. refresh tokens rotate,
+// so every process sharing the tokens has to go through the same store before refreshing
+#[async_trait::async_trait]
+pub trait TokenStore: Send + Sync {
+    async fn lease(&self, refresh_token: &str) -> Result<RefreshLease, anyhow::Error>;
+}
+
+#[async_trait::async_trait]
+pub trait TokenLease: Send {
+    async fn store(self: Box<Self>, tokens: &AccessTokenResponse) -> Result<(), anyhow::Error>;
+}
@@ -29,20 +29,29 @@ where
         tracing::Span::current().record("statusCode", resp.status().as_u16());
 
         // return the status error before trying to decode the response to propagate correct error
+        if let Some(e) = ClientError::from_status(resp.status(), resp.headers()) {
+            return Err(e);
+        }
+
         let resp = resp.error_for_status()?;
         let status = resp.status();
         let headers = resp.headers().clone();
-        let body = resp.json::<T>().await;
-
-        if let Err(ref e) = body {
-            tracing::error!("error deserialising maccas response: {e:?}");
-        };
-
-        Ok(Self {
-            status,
-            body: body?,
-            headers,
-        })
+        let text = resp.text().await?;
+
+        match serde_json::from_str::<T>(&text) {
+            Ok(body) => Ok(Self {
+                status,
+                body,
+                headers,
+            }),
+            Err(e) => {
+                tracing::error!("error deserialising maccas response: {e:?}");
+                Err(ClientError::SchemaMismatch {
+                    source: e,
+                    body: text,
+                })
+            }
+        }
     }
 }
 
@@ -341,6 +350,23 @@ pub struct Action {
     pub value: f64,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscountType {
+    // value is what each item costs, e.g. 2 hash browns for $1 each, see the captured
+    // response in converters/src/offers.rs
+    PricePoint,
+}
+
+impl Action {
+    // only the discount types we've seen a response for, guessing at the rest gets prices wrong
+    pub fn discount(&self) -> Option<DiscountType> {
+        match self.discount_type {
+            2 => Some(DiscountType::PricePoint),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FrequencyOfferInfo {
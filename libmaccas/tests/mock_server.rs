@@ -0,0 +1,183 @@
+use libmaccas::{
+    ApiClient, ClientError, RefreshLease, TokenLease, TokenStore,
+    testing::{Endpoint, Fault, MockResponse, MockServer},
+    types::response::{AccessTokenResponse, LoginRefreshResponse},
+};
+use std::sync::{Arc, Mutex};
+
+const CLIENT_ID: &str = "test-client";
+
+#[derive(Clone, Default)]
+struct MemoryTokenStore {
+    tokens: Arc<Mutex<Option<AccessTokenResponse>>>,
+}
+
+impl MemoryTokenStore {
+    fn stored(&self) -> Option<AccessTokenResponse> {
+        self.tokens.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenStore for MemoryTokenStore {
+    async fn lease(&self, refresh_token: &str) -> Result<RefreshLease, anyhow::Error> {
+        match self.stored() {
+            Some(tokens) if tokens.refresh_token != refresh_token => {
+                Ok(RefreshLease::Refreshed(tokens))
+            }
+            _ => Ok(RefreshLease::Acquired(Box::new(self.clone()))),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenLease for MemoryTokenStore {
+    async fn store(self: Box<Self>, tokens: &AccessTokenResponse) -> Result<(), anyhow::Error> {
+        *self.tokens.lock().unwrap() = Some(tokens.clone());
+        Ok(())
+    }
+}
+
+fn authenticated_client(server: &MockServer, store: Option<MemoryTokenStore>) -> ApiClient {
+    let mut api_client = server.api_client(CLIENT_ID);
+    api_client.set_auth_token("old-access-token");
+    api_client.set_refresh_token("old-refresh-token");
+    if let Some(store) = store {
+        api_client.set_token_store(store);
+    }
+
+    api_client
+}
+
+fn bearer(request: &libmaccas::testing::RecordedRequest) -> Option<&str> {
+    request
+        .headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+}
+
+#[tokio::test]
+async fn login() {
+    let server = MockServer::start().await.unwrap();
+    let mut api_client = server.api_client(CLIENT_ID);
+
+    let response = api_client.security_auth_token("secret").await.unwrap();
+    assert_eq!(response.body.response.token, "mock-login-token");
+    api_client.set_login_token(&response.body.response.token);
+
+    let requests = server.requests_for(Endpoint::SecurityAuthToken);
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].headers.get("mcd-clientid").unwrap(), CLIENT_ID);
+}
+
+#[tokio::test]
+async fn refreshes_and_retries_on_401() {
+    let server = MockServer::start().await.unwrap();
+    let store = MemoryTokenStore::default();
+    let api_client = authenticated_client(&server, Some(store.clone()));
+
+    server.fail_with(Endpoint::CustomerPoints, Fault::Unauthorized, 1);
+    api_client.get_customer_points().await.unwrap();
+
+    assert_eq!(server.requests_for(Endpoint::CustomerLoginRefresh).len(), 1);
+    let points = server.requests_for(Endpoint::CustomerPoints);
+    assert_eq!(points.len(), 2);
+    assert_eq!(bearer(&points[0]), Some("Bearer old-access-token"));
+    assert_eq!(bearer(&points[1]), Some("Bearer mock-access-token"));
+
+    let stored = store.stored().unwrap();
+    assert_eq!(stored.access_token, "mock-access-token");
+    assert_eq!(stored.refresh_token, "mock-refresh-token");
+}
+
+#[tokio::test]
+async fn concurrent_401s_refresh_once() {
+    let server = MockServer::start().await.unwrap();
+    let api_client = authenticated_client(&server, Some(MemoryTokenStore::default()));
+
+    server.fail_with(Endpoint::CustomerPoints, Fault::Unauthorized, 2);
+    let (a, b) = tokio::join!(
+        api_client.get_customer_points(),
+        api_client.get_customer_points()
+    );
+    a.unwrap();
+    b.unwrap();
+
+    assert_eq!(server.requests_for(Endpoint::CustomerLoginRefresh).len(), 1);
+}
+
+#[tokio::test]
+async fn uses_tokens_refreshed_elsewhere() {
+    let server = MockServer::start().await.unwrap();
+    let store = MemoryTokenStore::default();
+    *store.tokens.lock().unwrap() = Some(AccessTokenResponse {
+        access_token: "other-access-token".to_owned(),
+        refresh_token: "other-refresh-token".to_owned(),
+    });
+    let api_client = authenticated_client(&server, Some(store));
+
+    let tokens = api_client.refresh_tokens().await.unwrap();
+
+    assert_eq!(tokens.access_token, "other-access-token");
+    assert!(
+        server
+            .requests_for(Endpoint::CustomerLoginRefresh)
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn rejected_refresh_is_unauthorized() {
+    let server = MockServer::start().await.unwrap();
+    let store = MemoryTokenStore::default();
+    let api_client = authenticated_client(&server, Some(store.clone()));
+
+    server.respond_with(
+        Endpoint::CustomerLoginRefresh,
+        MockResponse::json(&LoginRefreshResponse::default()),
+    );
+    server.fail_with(Endpoint::CustomerPoints, Fault::Unauthorized, 1);
+
+    let result = api_client.get_customer_points().await;
+    assert!(matches!(result, Err(ClientError::Unauthorized)));
+    assert!(store.stored().is_none());
+}
+
+async fn fault_error(server: &MockServer, api_client: &ApiClient, fault: Fault) -> ClientError {
+    server.fail_with(Endpoint::CustomerPoints, fault, 1);
+    api_client.get_customer_points().await.unwrap_err()
+}
+
+#[tokio::test]
+async fn faults_map_to_client_errors() {
+    let server = MockServer::start().await.unwrap();
+    let api_client = authenticated_client(&server, None);
+
+    assert!(matches!(
+        fault_error(&server, &api_client, Fault::AkamaiBlock).await,
+        ClientError::Blocked
+    ));
+    assert!(matches!(
+        fault_error(&server, &api_client, Fault::Locked).await,
+        ClientError::AccountLocked
+    ));
+    assert!(matches!(
+        fault_error(&server, &api_client, Fault::Unauthorized).await,
+        ClientError::Unauthorized
+    ));
+    assert!(matches!(
+        fault_error(&server, &api_client, Fault::TooManyRequests).await,
+        ClientError::RateLimited { .. }
+    ));
+    assert!(matches!(
+        fault_error(&server, &api_client, Fault::ServerError).await,
+        ClientError::Upstream(status) if status.is_server_error()
+    ));
+    assert!(matches!(
+        fault_error(&server, &api_client, Fault::MalformedJson).await,
+        ClientError::SchemaMismatch { .. }
+    ));
+
+    // faults are consumed, the default response comes back afterwards
+    api_client.get_customer_points().await.unwrap();
+}
@@ -60,6 +60,22 @@ mod m20250426_154447_add_store_id_to_audit;
 mod m20250427_101811_add_pgmq;
 mod m20250703_121907_add_concurrent_active_deals;
 mod m20260208_091114_add_cancelled_status;
+mod m20260301_093012_add_products_and_prices;
+mod m20260314_101544_add_store_details;
+mod m20260402_083120_add_store_suburb_and_postcode;
+mod m20260412_094501_add_offer_audit_user_index;
+mod m20260419_110233_add_user_deal_rollup;
+mod m20260503_081342_add_offer_waitlist;
+mod m20260510_074215_add_user_limits;
+mod m20260517_083041_add_deal_extension;
+mod m20260524_090517_add_active_transactions_view;
+mod m20260531_074408_add_offer_details_search_index;
+mod m20260607_091527_add_offer_history_proposition_index;
+mod m20260614_082230_add_push_notifications;
+mod m20260621_073915_add_notification_deliveries;
+mod m20260628_084512_add_notification_delivery_log;
+mod m20260705_081233_add_discord_users;
+mod m20260712_083317_add_store_search_areas;
 
 pub struct Migrator;
 
@@ -127,6 +143,22 @@ impl MigratorTrait for Migrator {
             Box::new(m20250427_101811_add_pgmq::Migration),
             Box::new(m20250703_121907_add_concurrent_active_deals::Migration),
             Box::new(m20260208_091114_add_cancelled_status::Migration),
+            Box::new(m20260301_093012_add_products_and_prices::Migration),
+            Box::new(m20260314_101544_add_store_details::Migration),
+            Box::new(m20260402_083120_add_store_suburb_and_postcode::Migration),
+            Box::new(m20260412_094501_add_offer_audit_user_index::Migration),
+            Box::new(m20260419_110233_add_user_deal_rollup::Migration),
+            Box::new(m20260503_081342_add_offer_waitlist::Migration),
+            Box::new(m20260510_074215_add_user_limits::Migration),
+            Box::new(m20260517_083041_add_deal_extension::Migration),
+            Box::new(m20260524_090517_add_active_transactions_view::Migration),
+            Box::new(m20260531_074408_add_offer_details_search_index::Migration),
+            Box::new(m20260607_091527_add_offer_history_proposition_index::Migration),
+            Box::new(m20260614_082230_add_push_notifications::Migration),
+            Box::new(m20260621_073915_add_notification_deliveries::Migration),
+            Box::new(m20260628_084512_add_notification_delivery_log::Migration),
+            Box::new(m20260705_081233_add_discord_users::Migration),
+            Box::new(m20260712_083317_add_store_search_areas::Migration),
         ]
     }
 }
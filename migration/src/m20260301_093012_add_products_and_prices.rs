@@ -0,0 +1,123 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum Products {
+    Table,
+    ProductCode,
+    Name,
+    ShortName,
+    ImageName,
+    Kilojoules,
+    Nutrition,
+    CategoryIds,
+    MenuTypeId,
+    IsPromotional,
+    IsSalable,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ProductPrices {
+    Table,
+    StoreId,
+    ProductCode,
+    PriceTypeId,
+    Price,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Products::Table)
+                    .if_not_exists()
+                    .col(big_integer(Products::ProductCode).primary_key())
+                    .col(string(Products::Name))
+                    .col(string_null(Products::ShortName))
+                    .col(string(Products::ImageName))
+                    .col(double_null(Products::Kilojoules))
+                    .col(json_binary_null(Products::Nutrition))
+                    .col(array(Products::CategoryIds, ColumnType::Integer))
+                    .col(big_integer(Products::MenuTypeId))
+                    .col(boolean(Products::IsPromotional))
+                    .col(boolean(Products::IsSalable))
+                    .col(date_time(Products::CreatedAt).default(Expr::current_timestamp()))
+                    .col(date_time(Products::UpdatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProductPrices::Table)
+                    .if_not_exists()
+                    .col(string(ProductPrices::StoreId))
+                    .col(big_integer(ProductPrices::ProductCode))
+                    .col(big_integer(ProductPrices::PriceTypeId))
+                    .col(double(ProductPrices::Price))
+                    .col(date_time(ProductPrices::CreatedAt).default(Expr::current_timestamp()))
+                    .col(date_time(ProductPrices::UpdatedAt).default(Expr::current_timestamp()))
+                    .primary_key(
+                        Index::create()
+                            .col(ProductPrices::StoreId)
+                            .col(ProductPrices::ProductCode)
+                            .col(ProductPrices::PriceTypeId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("product_prices_product_code")
+                            .from(ProductPrices::Table, ProductPrices::ProductCode)
+                            .to(Products::Table, Products::ProductCode)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("product_prices_product_code_idx")
+                    .table(ProductPrices::Table)
+                    .col(ProductPrices::ProductCode)
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        db.execute_unprepared(r#"
+                    CREATE TRIGGER update_products_updated_at BEFORE UPDATE ON products FOR EACH ROW EXECUTE PROCEDURE set_updated_at_column();
+                    CREATE TRIGGER update_product_prices_updated_at BEFORE UPDATE ON product_prices FOR EACH ROW EXECUTE PROCEDURE set_updated_at_column();
+                    "#).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "DROP TRIGGER IF EXISTS update_product_prices_updated_at ON product_prices",
+        )
+        .await?;
+        db.execute_unprepared("DROP TRIGGER IF EXISTS update_products_updated_at ON products")
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ProductPrices::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Products::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,80 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum Stores {
+    Table,
+    Latitude,
+    Longitude,
+    TimeZone,
+    OpeningHours,
+    Facilities,
+    DriveThru,
+    McCafe,
+    #[sea_orm(iden = "open_24h")]
+    Open24h,
+    TableService,
+    MobileOrder,
+    UpdatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stores::Table)
+                    .add_column(double_null(Stores::Latitude))
+                    .add_column(double_null(Stores::Longitude))
+                    .add_column(string_null(Stores::TimeZone))
+                    .add_column(json_binary_null(Stores::OpeningHours))
+                    .add_column(
+                        array(Stores::Facilities, ColumnType::Text).default(Expr::cust("'{}'")),
+                    )
+                    .add_column(boolean(Stores::DriveThru).default(false))
+                    .add_column(boolean(Stores::McCafe).default(false))
+                    .add_column(boolean(Stores::Open24h).default(false))
+                    .add_column(boolean(Stores::TableService).default(false))
+                    .add_column(boolean(Stores::MobileOrder).default(false))
+                    .add_column(date_time(Stores::UpdatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "CREATE TRIGGER update_stores_updated_at BEFORE UPDATE ON stores FOR EACH ROW EXECUTE PROCEDURE set_updated_at_column();",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP TRIGGER IF EXISTS update_stores_updated_at ON stores")
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stores::Table)
+                    .drop_column(Stores::Latitude)
+                    .drop_column(Stores::Longitude)
+                    .drop_column(Stores::TimeZone)
+                    .drop_column(Stores::OpeningHours)
+                    .drop_column(Stores::Facilities)
+                    .drop_column(Stores::DriveThru)
+                    .drop_column(Stores::McCafe)
+                    .drop_column(Stores::Open24h)
+                    .drop_column(Stores::TableService)
+                    .drop_column(Stores::MobileOrder)
+                    .drop_column(Stores::UpdatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
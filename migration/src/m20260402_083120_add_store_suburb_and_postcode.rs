@@ -0,0 +1,67 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum Stores {
+    Table,
+    Suburb,
+    Postcode,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stores::Table)
+                    .add_column(string_null(Stores::Suburb))
+                    .add_column(string_null(Stores::Postcode))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("stores_postcode_idx")
+                    .table(Stores::Table)
+                    .col(Stores::Postcode)
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        db.execute_unprepared("CREATE INDEX stores_lower_suburb_idx ON stores (lower(suburb))")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP INDEX IF EXISTS stores_lower_suburb_idx")
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("stores_postcode_idx")
+                    .table(Stores::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stores::Table)
+                    .drop_column(Stores::Suburb)
+                    .drop_column(Stores::Postcode)
+                    .to_owned(),
+            )
+            .await
+    }
+}
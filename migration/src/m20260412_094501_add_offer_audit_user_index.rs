@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum OfferAudit {
+    Table,
+    UserId,
+    Id,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .name("offer_audit_user_id_id_idx")
+                    .table(OfferAudit::Table)
+                    .col(OfferAudit::UserId)
+                    .col(OfferAudit::Id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("offer_audit_user_id_id_idx")
+                    .table(OfferAudit::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // hourly redemptions per user, refreshed by the refresh_user_stats job.
+        // estimated_savings follows OfferSavings::calculate: each product set is priced at
+        // its cheapest product on the store's menu, and a deal counts for nothing unless
+        // every set has a menu price and a known discount type (2, the price point)
+        db.execute_unprepared(
+            r#"
+            CREATE MATERIALIZED VIEW user_deal_rollup AS
+            SELECT
+                a.user_id,
+                date_trunc('hour', a.created_at) AS hour,
+                a.proposition_id,
+                COALESCE(a.store_id, '') AS store_id,
+                count(*)::bigint AS deals,
+                count(*) FILTER (WHERE a.likely_used)::bigint AS likely_used,
+                COALESCE(sum(d.price), 0)::double precision AS total_spent,
+                COALESCE(sum(s.savings), 0)::double precision AS estimated_savings
+            FROM offer_audit a
+            LEFT JOIN offer_details d ON d.proposition_id = a.proposition_id
+            LEFT JOIN LATERAL (
+                SELECT
+                    CASE WHEN bool_and(p.menu_price IS NOT NULL)
+                        AND bool_and(p.discount_type IS NULL OR p.discount_type = 2)
+                    THEN round(
+                        sum(p.menu_price * p.quantity)::numeric
+                            - sum(COALESCE(p.action_value, p.menu_price) * p.quantity)::numeric,
+                        2
+                    )
+                    END AS savings
+                FROM (
+                    SELECT
+                        greatest((ps->>'quantity')::bigint, 1) AS quantity,
+                        (ps->'action'->>'discountType')::bigint AS discount_type,
+                        (ps->'action'->>'value')::double precision AS action_value,
+                        (
+                            SELECT min(pp.price)
+                            FROM jsonb_array_elements_text(ps->'products') AS code
+                            JOIN product_prices pp
+                                ON pp.product_code = code::bigint AND pp.store_id = a.store_id
+                            WHERE code ~ '^[0-9]+$'
+                        ) AS menu_price
+                    FROM jsonb_array_elements(COALESCE(d.raw_data->'productSets', '[]')) AS ps
+                ) p
+            ) s ON true
+            WHERE a.action = 'add' AND a.user_id IS NOT NULL
+            GROUP BY 1, 2, 3, 4;
+
+            CREATE UNIQUE INDEX user_deal_rollup_key_idx
+                ON user_deal_rollup (user_id, hour, proposition_id, store_id);
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP MATERIALIZED VIEW IF EXISTS user_deal_rollup")
+            .await?;
+
+        Ok(())
+    }
+}
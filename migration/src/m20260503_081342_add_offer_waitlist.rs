@@ -0,0 +1,62 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum OfferWaitlist {
+    Table,
+    Id,
+    UserId,
+    OfferPropositionId,
+    NotifiedAt,
+    CreatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OfferWaitlist::Table)
+                    .if_not_exists()
+                    .col(pk_auto(OfferWaitlist::Id))
+                    .col(uuid(OfferWaitlist::UserId))
+                    .col(big_integer(OfferWaitlist::OfferPropositionId))
+                    .col(date_time_null(OfferWaitlist::NotifiedAt))
+                    .col(date_time(OfferWaitlist::CreatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("offer_waitlist_user_id_proposition_id_key")
+                    .table(OfferWaitlist::Table)
+                    .col(OfferWaitlist::UserId)
+                    .col(OfferWaitlist::OfferPropositionId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("offer_waitlist_proposition_id_id_idx")
+                    .table(OfferWaitlist::Table)
+                    .col(OfferWaitlist::OfferPropositionId)
+                    .col(OfferWaitlist::Id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OfferWaitlist::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,58 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum UserLimits {
+    Table,
+    UserId,
+    ConcurrentLimit,
+    DailyLimit,
+    WeeklyLimit,
+    OfferCooldownSeconds,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // null falls back to the limits in settings
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserLimits::Table)
+                    .if_not_exists()
+                    .col(uuid(UserLimits::UserId).primary_key())
+                    .col(integer_null(UserLimits::ConcurrentLimit))
+                    .col(integer_null(UserLimits::DailyLimit))
+                    .col(integer_null(UserLimits::WeeklyLimit))
+                    .col(big_integer_null(UserLimits::OfferCooldownSeconds))
+                    .col(date_time(UserLimits::CreatedAt).default(Expr::current_timestamp()))
+                    .col(date_time(UserLimits::UpdatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "CREATE TRIGGER update_user_limits_updated_at BEFORE UPDATE ON user_limits FOR EACH ROW EXECUTE PROCEDURE set_updated_at_column();",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "DROP TRIGGER IF EXISTS update_user_limits_updated_at ON user_limits",
+        )
+        .await?;
+
+        manager
+            .drop_table(Table::drop().table(UserLimits::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,52 @@
+use sea_orm_migration::{prelude::*, schema::*, sea_query::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Action {
+    #[sea_orm(iden = "action")]
+    Type,
+    Extend,
+}
+
+#[derive(DeriveIden)]
+enum Events {
+    Table,
+    MessageId,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_type(
+                Type::alter()
+                    .name(Action::Type)
+                    .add_value(Action::Extend)
+                    .to_owned(),
+            )
+            .await?;
+
+        // the pgmq message backing the event, so it can be rescheduled
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Events::Table)
+                    .add_column(big_integer_null(Events::MessageId))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Events::Table)
+                    .drop_column(Events::MessageId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
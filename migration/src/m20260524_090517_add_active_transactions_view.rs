@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // a deal is active while its cleanup hasn't run, nothing removed or used it and the
+        // account it was taken from is still locked
+        db.execute_unprepared(
+            r#"
+            CREATE INDEX events_cleanup_transaction_id_idx
+                ON events ((data -> 'Cleanup' ->> 'transaction_id'))
+                WHERE name = 'Cleanup' AND status IN ('pending', 'running');
+
+            CREATE VIEW active_transactions AS
+            SELECT DISTINCT
+                a.transaction_id,
+                a.user_id,
+                a.proposition_id,
+                l.id AS account_id,
+                a.created_at,
+                l.unlock_at
+            FROM offer_audit a
+            JOIN events e
+                ON e.name = 'Cleanup'
+                AND e.status IN ('pending', 'running')
+                AND e.data -> 'Cleanup' ->> 'transaction_id' = a.transaction_id::text
+            JOIN account_lock l ON l.id = (e.data -> 'Cleanup' ->> 'account_id')::uuid
+            WHERE a.action = 'add'
+                AND a.user_id IS NOT NULL
+                AND a.likely_used IS NOT TRUE
+                AND NOT EXISTS (
+                    SELECT 1 FROM offer_audit r
+                    WHERE r.transaction_id = a.transaction_id AND r.action = 'remove'
+                );
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP VIEW IF EXISTS active_transactions")
+            .await?;
+        db.execute_unprepared("DROP INDEX IF EXISTS events_cleanup_transaction_id_idx")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // the expression has to match the one searchOffers ranks with for this to be used
+        db.execute_unprepared(
+            r#"
+            CREATE INDEX offer_details_search_idx
+                ON offer_details
+                USING GIN (to_tsvector('english', name || ' ' || description));
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP INDEX IF EXISTS offer_details_search_idx")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum OfferHistory {
+    Table,
+    OfferPropositionId,
+    ValidFrom,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .name("offer_history_offer_proposition_id_valid_from_idx")
+                    .table(OfferHistory::Table)
+                    .col(OfferHistory::OfferPropositionId)
+                    .col(OfferHistory::ValidFrom)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("offer_history_offer_proposition_id_valid_from_idx")
+                    .table(OfferHistory::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,153 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum PushSubscriptions {
+    Table,
+    Id,
+    UserId,
+    Endpoint,
+    P256dh,
+    Auth,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum NotificationRules {
+    Table,
+    Id,
+    UserId,
+    Categories,
+    Keywords,
+    SimilarToUsed,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum OfferFavourites {
+    Table,
+    UserId,
+    ShortName,
+    CreatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PushSubscriptions::Table)
+                    .if_not_exists()
+                    .col(pk_auto(PushSubscriptions::Id))
+                    .col(uuid(PushSubscriptions::UserId))
+                    .col(text_uniq(PushSubscriptions::Endpoint))
+                    .col(string(PushSubscriptions::P256dh))
+                    .col(string(PushSubscriptions::Auth))
+                    .col(date_time(PushSubscriptions::CreatedAt).default(Expr::current_timestamp()))
+                    .col(date_time(PushSubscriptions::UpdatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("push_subscriptions_user_id_idx")
+                    .table(PushSubscriptions::Table)
+                    .col(PushSubscriptions::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // a rule matches when any of its conditions do
+        manager
+            .create_table(
+                Table::create()
+                    .table(NotificationRules::Table)
+                    .if_not_exists()
+                    .col(pk_auto(NotificationRules::Id))
+                    .col(uuid(NotificationRules::UserId))
+                    .col(
+                        array(NotificationRules::Categories, ColumnType::Text)
+                            .default(Expr::cust("'{}'")),
+                    )
+                    .col(
+                        array(NotificationRules::Keywords, ColumnType::Text)
+                            .default(Expr::cust("'{}'")),
+                    )
+                    .col(boolean(NotificationRules::SimilarToUsed).default(false))
+                    .col(date_time(NotificationRules::CreatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("notification_rules_user_id_idx")
+                    .table(NotificationRules::Table)
+                    .col(NotificationRules::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(OfferFavourites::Table)
+                    .if_not_exists()
+                    .col(uuid(OfferFavourites::UserId))
+                    .col(string(OfferFavourites::ShortName))
+                    .col(date_time(OfferFavourites::CreatedAt).default(Expr::current_timestamp()))
+                    .primary_key(
+                        Index::create()
+                            .col(OfferFavourites::UserId)
+                            .col(OfferFavourites::ShortName),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("offer_favourites_short_name_idx")
+                    .table(OfferFavourites::Table)
+                    .col(OfferFavourites::ShortName)
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "CREATE TRIGGER update_push_subscriptions_updated_at BEFORE UPDATE ON push_subscriptions FOR EACH ROW EXECUTE PROCEDURE set_updated_at_column();",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "DROP TRIGGER IF EXISTS update_push_subscriptions_updated_at ON push_subscriptions",
+        )
+        .await?;
+
+        manager
+            .drop_table(Table::drop().table(OfferFavourites::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(NotificationRules::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(PushSubscriptions::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,90 @@
+use sea_orm_migration::{prelude::*, schema::*, sea_query::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum DeliveryStatus {
+    #[sea_orm(iden = "delivery_status")]
+    Type,
+    Delivered,
+    Failed,
+}
+
+#[derive(DeriveIden)]
+enum NotificationDeliveries {
+    Table,
+    Id,
+    Channel,
+    Target,
+    OfferPropositionId,
+    Status,
+    ResponseStatus,
+    Error,
+    CreatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(DeliveryStatus::Type)
+                    .values([DeliveryStatus::Delivered, DeliveryStatus::Failed])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(NotificationDeliveries::Table)
+                    .if_not_exists()
+                    .col(pk_auto(NotificationDeliveries::Id))
+                    .col(string(NotificationDeliveries::Channel))
+                    .col(string(NotificationDeliveries::Target))
+                    .col(big_integer(NotificationDeliveries::OfferPropositionId))
+                    .col(
+                        ColumnDef::new(NotificationDeliveries::Status)
+                            .custom(DeliveryStatus::Type)
+                            .not_null(),
+                    )
+                    .col(integer_null(NotificationDeliveries::ResponseStatus))
+                    .col(text_null(NotificationDeliveries::Error))
+                    .col(
+                        date_time(NotificationDeliveries::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // rate limiting looks up the latest delivery per channel and target
+        manager
+            .create_index(
+                Index::create()
+                    .name("notification_deliveries_channel_target_id_idx")
+                    .table(NotificationDeliveries::Table)
+                    .col(NotificationDeliveries::Channel)
+                    .col(NotificationDeliveries::Target)
+                    .col(NotificationDeliveries::Id)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(NotificationDeliveries::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(DeliveryStatus::Type).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,47 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum NotificationDeliveries {
+    Table,
+    RequestBody,
+    LatencyMs,
+    Attempt,
+    RetryOf,
+    NextAttemptAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NotificationDeliveries::Table)
+                    .add_column_if_not_exists(json_binary_null(NotificationDeliveries::RequestBody))
+                    .add_column_if_not_exists(integer_null(NotificationDeliveries::LatencyMs))
+                    .add_column_if_not_exists(integer(NotificationDeliveries::Attempt).default(1))
+                    .add_column_if_not_exists(integer_null(NotificationDeliveries::RetryOf))
+                    .add_column_if_not_exists(date_time_null(NotificationDeliveries::NextAttemptAt))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NotificationDeliveries::Table)
+                    .drop_column(NotificationDeliveries::RequestBody)
+                    .drop_column(NotificationDeliveries::LatencyMs)
+                    .drop_column(NotificationDeliveries::Attempt)
+                    .drop_column(NotificationDeliveries::RetryOf)
+                    .drop_column(NotificationDeliveries::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
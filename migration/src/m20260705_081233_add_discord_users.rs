@@ -0,0 +1,48 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum DiscordUsers {
+    Table,
+    UserId,
+    DiscordUserId,
+    Roles,
+    RolesExpiresAt,
+    LinkCode,
+    LinkCodeExpiresAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // a row exists from when the link code is issued, discord_user_id is set once
+        // it's redeemed with /link. roles are a copy from the web app's token, they stop
+        // counting at roles_expires_at and the user has to link again
+        manager
+            .create_table(
+                Table::create()
+                    .table(DiscordUsers::Table)
+                    .if_not_exists()
+                    .col(uuid(DiscordUsers::UserId).primary_key())
+                    .col(string_null(DiscordUsers::DiscordUserId).unique_key())
+                    .col(json_binary(DiscordUsers::Roles).default(Expr::cust("'[]'")))
+                    .col(date_time(DiscordUsers::RolesExpiresAt).default(Expr::current_timestamp()))
+                    .col(string_null(DiscordUsers::LinkCode).unique_key())
+                    .col(date_time_null(DiscordUsers::LinkCodeExpiresAt))
+                    .col(date_time(DiscordUsers::CreatedAt).default(Expr::current_timestamp()))
+                    .col(date_time(DiscordUsers::UpdatedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DiscordUsers::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,40 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum StoreSearchAreas {
+    Table,
+    Id,
+    Latitude,
+    Longitude,
+    RadiusMeters,
+    SearchedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // circles sync_stores got an answer for, every store inside one is in stores
+        manager
+            .create_table(
+                Table::create()
+                    .table(StoreSearchAreas::Table)
+                    .if_not_exists()
+                    .col(pk_auto(StoreSearchAreas::Id))
+                    .col(double(StoreSearchAreas::Latitude))
+                    .col(double(StoreSearchAreas::Longitude))
+                    .col(double(StoreSearchAreas::RadiusMeters))
+                    .col(date_time(StoreSearchAreas::SearchedAt).default(Expr::current_timestamp()))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(StoreSearchAreas::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,21 @@
+use crate::types::{Area, ClientResult, Location, Rectangle};
+
+// Australia square, low -> high
+pub const AUSTRALIA: Area = Area {
+    rectangle: Rectangle {
+        low: Location {
+            latitude: -46.2858922444765,
+            longitude: 109.62638287960314,
+        },
+        high: Location {
+            latitude: -10.481731180947541,
+            longitude: 156.54739153571109,
+        },
+    },
+};
+
+#[async_trait::async_trait]
+pub trait Geocoder: Send + Sync {
+    // best match for a free text query, biased to Australia
+    async fn geocode(&self, query: &str) -> ClientResult<Option<Location>>;
+}
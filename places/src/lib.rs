@@ -1,10 +1,16 @@
+use geocoder::AUSTRALIA;
 use http::Method;
 use reqwest_middleware::{ClientWithMiddleware, RequestBuilder};
 use tracing::instrument;
-use types::{ClientResponse, ClientResult, PlacesRequest, PlacesResponse};
+use types::{ClientResponse, ClientResult, Location, PlacesRequest, PlacesResponse};
 
+pub mod geocoder;
+pub mod osm;
+pub mod stores;
 pub mod types;
 
+pub use geocoder::Geocoder;
+
 pub struct ApiClient {
     api_key: String,
     client: ClientWithMiddleware,
@@ -38,3 +44,18 @@ impl ApiClient {
         ClientResponse::from_response(response).await
     }
 }
+
+#[async_trait::async_trait]
+impl Geocoder for ApiClient {
+    async fn geocode(&self, query: &str) -> ClientResult<Option<Location>> {
+        let response = self
+            .get_place_by_text(&PlacesRequest {
+                text_query: query.to_owned(),
+                max_result_count: 1,
+                location_bias: AUSTRALIA,
+            })
+            .await?;
+
+        Ok(response.body.places.into_iter().next().map(|p| p.location))
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{
+    geocoder::{AUSTRALIA, Geocoder},
+    types::{ClientResponse, ClientResult, Location, NominatimPlace, PhotonResponse},
+};
+use http::{Method, header::USER_AGENT};
+use reqwest_middleware::{ClientWithMiddleware, RequestBuilder};
+use tracing::instrument;
+
+pub const NOMINATIM_BASE_URL: &str = "https://nominatim.openstreetmap.org";
+pub const PHOTON_BASE_URL: &str = "https://photon.komoot.io";
+
+// the public instances reject requests without an identifying user agent
+const OSM_USER_AGENT: &str = "maccas-api";
+
+fn get_default_request(
+    client: &ClientWithMiddleware,
+    base_url: &str,
+    resource: &str,
+) -> RequestBuilder {
+    client
+        .request(
+            Method::GET,
+            format!("{}/{}", base_url.trim_end_matches('/'), resource),
+        )
+        .header(USER_AGENT, OSM_USER_AGENT)
+}
+
+pub struct NominatimClient {
+    base_url: String,
+    client: ClientWithMiddleware,
+}
+
+impl NominatimClient {
+    pub fn new(base_url: String, client: ClientWithMiddleware) -> Self {
+        Self { base_url, client }
+    }
+
+    // /search?format=jsonv2
+    #[instrument(skip(self), fields(statusCode))]
+    pub async fn search(&self, query: &str) -> ClientResult<ClientResponse<Vec<NominatimPlace>>> {
+        let request = get_default_request(&self.client, &self.base_url, "search").query(&[
+            ("q", query),
+            ("format", "jsonv2"),
+            ("limit", "1"),
+            ("countrycodes", "au"),
+        ]);
+
+        let response = request.send().await?;
+        ClientResponse::from_response(response).await
+    }
+}
+
+#[async_trait::async_trait]
+impl Geocoder for NominatimClient {
+    async fn geocode(&self, query: &str) -> ClientResult<Option<Location>> {
+        let response = self.search(query).await?;
+
+        Ok(response.body.first().and_then(|place| {
+            Some(Location {
+                latitude: place.lat.parse().ok()?,
+                longitude: place.lon.parse().ok()?,
+            })
+        }))
+    }
+}
+
+pub struct PhotonClient {
+    base_url: String,
+    client: ClientWithMiddleware,
+}
+
+impl PhotonClient {
+    pub fn new(base_url: String, client: ClientWithMiddleware) -> Self {
+        Self { base_url, client }
+    }
+
+    // /api?q=
+    #[instrument(skip(self), fields(statusCode))]
+    pub async fn search(&self, query: &str) -> ClientResult<ClientResponse<PhotonResponse>> {
+        let area = &AUSTRALIA.rectangle;
+        let bbox = format!(
+            "{},{},{},{}",
+            area.low.longitude, area.low.latitude, area.high.longitude, area.high.latitude
+        );
+
+        let request = get_default_request(&self.client, &self.base_url, "api").query(&[
+            ("q", query),
+            ("limit", "1"),
+            ("bbox", &bbox),
+        ]);
+
+        let response = request.send().await?;
+        ClientResponse::from_response(response).await
+    }
+}
+
+#[async_trait::async_trait]
+impl Geocoder for PhotonClient {
+    async fn geocode(&self, query: &str) -> ClientResult<Option<Location>> {
+        let response = self.search(query).await?;
+
+        Ok(response.body.features.first().and_then(|feature| {
+            match feature.geometry.coordinates[..] {
+                [longitude, latitude, ..] => Some(Location {
+                    latitude,
+                    longitude,
+                }),
+                _ => None,
+            }
+        }))
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{
+    geocoder::Geocoder,
+    types::{ClientResult, Location},
+};
+
+const STATES: &[&str] = &["nsw", "vic", "qld", "wa", "sa", "tas", "act", "nt"];
+
+// coordinates of the synced stores, implemented where the stores live so this crate
+// doesn't need the database
+#[async_trait::async_trait]
+pub trait StoreLocations: Send + Sync {
+    async fn in_postcode(&self, postcode: &str) -> ClientResult<Vec<Location>>;
+    // case insensitive, the whole suburb name
+    async fn in_suburb(&self, suburb: &str) -> ClientResult<Vec<Location>>;
+}
+
+// offline, resolves a suburb or postcode to the middle of the stores in it
+pub struct StoresGeocoder<S> {
+    stores: S,
+}
+
+impl<S> StoresGeocoder<S> {
+    pub fn new(stores: S) -> Self {
+        Self { stores }
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: StoreLocations> Geocoder for StoresGeocoder<S> {
+    async fn geocode(&self, query: &str) -> ClientResult<Option<Location>> {
+        // "Parramatta NSW 2150" -> suburb "parramatta", postcode "2150"
+        let query = query.to_lowercase().replace(',', " ");
+        let (postcodes, words): (Vec<&str>, Vec<&str>) = query
+            .split_whitespace()
+            .filter(|w| !STATES.contains(w) && *w != "australia")
+            .partition(|w| w.len() == 4 && w.chars().all(|c| c.is_ascii_digit()));
+        let suburb = words.join(" ");
+
+        let locations = match (postcodes.first(), suburb.is_empty()) {
+            (Some(postcode), _) => self.stores.in_postcode(postcode).await?,
+            (None, false) => self.stores.in_suburb(&suburb).await?,
+            (None, true) => return Ok(None),
+        };
+
+        if locations.is_empty() {
+            return Ok(None);
+        }
+
+        let count = locations.len() as f64;
+        let (latitude, longitude) = locations.iter().fold((0.0, 0.0), |(lat, long), l| {
+            (lat + l.latitude, long + l.longitude)
+        });
+
+        Ok(Some(Location {
+            latitude: latitude / count,
+            longitude: longitude / count,
+        }))
+    }
+}
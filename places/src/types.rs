@@ -39,6 +39,8 @@ pub enum ClientError {
     RequestError(#[from] reqwest::Error),
     #[error("serialization error")]
     SerializationError(#[from] serde_json::Error),
+    #[error("geocoder backend error")]
+    Backend(#[source] Box<dyn std::error::Error + Send + Sync>),
     #[error("unknown error")]
     Unknown,
 }
@@ -89,3 +91,26 @@ pub struct DisplayName {
     pub text: String,
     pub language_code: String,
 }
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NominatimPlace {
+    pub lat: String,
+    pub lon: String,
+    pub display_name: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhotonResponse {
+    pub features: Vec<PhotonFeature>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhotonFeature {
+    pub geometry: PhotonGeometry,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhotonGeometry {
+    // geojson order, longitude first
+    pub coordinates: Vec<f64>,
+}
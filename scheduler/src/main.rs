@@ -65,6 +65,46 @@ create_trigger_fn!(
     trigger_activate_account
 );
 
+create_trigger_fn!(
+    CreateEvent {
+        event: ::api::Event::RefreshMenuCatalog {},
+        delay: Duration::from_secs(5),
+    },
+    trigger_refresh_menu_catalog
+);
+
+create_trigger_fn!(
+    CreateEvent {
+        event: ::api::Event::SyncStores {},
+        delay: Duration::from_secs(5),
+    },
+    trigger_sync_stores
+);
+
+create_trigger_fn!(
+    CreateEvent {
+        event: ::api::Event::RefreshUserStats {},
+        delay: Duration::from_secs(5),
+    },
+    trigger_refresh_user_stats
+);
+
+create_trigger_fn!(
+    CreateEvent {
+        event: ::api::Event::ReconcileActiveDeals {},
+        delay: Duration::from_secs(5),
+    },
+    trigger_reconcile_active_deals
+);
+
+create_trigger_fn!(
+    CreateEvent {
+        event: ::api::Event::PruneWaitlist {},
+        delay: Duration::from_secs(5),
+    },
+    trigger_prune_waitlist
+);
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     base::tracing::init("scheduler");
@@ -76,6 +116,12 @@ async fn main() -> anyhow::Result<()> {
         create_worker!("0 0 * * * *", trigger_generate_recommendations);
     let _create_account_worker = create_worker!("0 */15 * * * *", trigger_create_account);
     let _activate_account_worker = create_worker!("0 */5 * * * *", trigger_activate_account);
+    let refresh_menu_catalog_worker = create_worker!("0 0 18 * * *", trigger_refresh_menu_catalog);
+    let sync_stores_worker = create_worker!("0 0 17 * * SUN", trigger_sync_stores);
+    let refresh_user_stats_worker = create_worker!("0 */15 * * * *", trigger_refresh_user_stats);
+    let reconcile_active_deals_worker =
+        create_worker!("0 */10 * * * *", trigger_reconcile_active_deals);
+    let prune_waitlist_worker = create_worker!("0 */15 * * * *", trigger_prune_waitlist);
 
     tracing::info!("scheduler started");
     Monitor::new()
@@ -83,6 +129,11 @@ async fn main() -> anyhow::Result<()> {
         .register(account_unlock_worker)
         .register(categorise_offers_worker)
         .register(generate_recommendations_worker)
+        .register(refresh_menu_catalog_worker)
+        .register(sync_stores_worker)
+        .register(refresh_user_stats_worker)
+        .register(reconcile_active_deals_worker)
+        .register(prune_waitlist_worker)
         // .register(create_account_worker)
         // .register(activate_account_worker)
         .run()